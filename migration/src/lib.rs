@@ -1,14 +1,73 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20220101_000002_create_keyword_alias;
+mod m20220101_000003_add_keyword_conditions;
+mod m20220101_000004_create_group_subscription;
+mod m20220101_000005_create_group_setting;
+mod m20220101_000006_create_user_session;
+mod m20220101_000007_create_group_role;
+mod m20220101_000008_add_list_restricted;
+mod m20220101_000009_create_keyword_audit;
+mod m20220101_000010_create_keyword_trash;
+mod m20220101_000011_add_audit_undo;
+mod m20220101_000012_create_keyword_stat;
+mod m20220101_000013_create_daily_hit;
+mod m20220101_000014_add_cooldowns;
+mod m20220101_000015_add_message_filters;
+mod m20220101_000016_add_keyword_actions;
+mod m20220101_000017_create_user_warning;
+mod m20220101_000018_add_greetings;
+mod m20220101_000019_create_captcha_challenge;
+mod m20220101_000020_create_scheduled_message;
 
 pub use m20220101_000001_create_table::GroupReply;
+pub use m20220101_000002_create_keyword_alias::KeywordAlias;
+pub use m20220101_000003_add_keyword_conditions::KeywordConditions;
+pub use m20220101_000004_create_group_subscription::GroupSubscription;
+pub use m20220101_000005_create_group_setting::GroupSetting;
+pub use m20220101_000006_create_user_session::UserSession;
+pub use m20220101_000007_create_group_role::GroupRole;
+pub use m20220101_000008_add_list_restricted::ListRestricted;
+pub use m20220101_000009_create_keyword_audit::KeywordAudit;
+pub use m20220101_000010_create_keyword_trash::KeywordTrash;
+pub use m20220101_000011_add_audit_undo::AuditUndo;
+pub use m20220101_000012_create_keyword_stat::KeywordStat;
+pub use m20220101_000013_create_daily_hit::DailyHit;
+pub use m20220101_000014_add_cooldowns::Cooldowns;
+pub use m20220101_000015_add_message_filters::{IgnoredUser, MessageFilters};
+pub use m20220101_000016_add_keyword_actions::KeywordActions;
+pub use m20220101_000017_create_user_warning::{ModerationLog, UserWarning, WarnThresholds};
+pub use m20220101_000018_add_greetings::Greetings;
+pub use m20220101_000019_create_captcha_challenge::{CaptchaChallenge, CaptchaSettings};
+pub use m20220101_000020_create_scheduled_message::ScheduledMessage;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20220101_000002_create_keyword_alias::Migration),
+            Box::new(m20220101_000003_add_keyword_conditions::Migration),
+            Box::new(m20220101_000004_create_group_subscription::Migration),
+            Box::new(m20220101_000005_create_group_setting::Migration),
+            Box::new(m20220101_000006_create_user_session::Migration),
+            Box::new(m20220101_000007_create_group_role::Migration),
+            Box::new(m20220101_000008_add_list_restricted::Migration),
+            Box::new(m20220101_000009_create_keyword_audit::Migration),
+            Box::new(m20220101_000010_create_keyword_trash::Migration),
+            Box::new(m20220101_000011_add_audit_undo::Migration),
+            Box::new(m20220101_000012_create_keyword_stat::Migration),
+            Box::new(m20220101_000013_create_daily_hit::Migration),
+            Box::new(m20220101_000014_add_cooldowns::Migration),
+            Box::new(m20220101_000015_add_message_filters::Migration),
+            Box::new(m20220101_000016_add_keyword_actions::Migration),
+            Box::new(m20220101_000017_create_user_warning::Migration),
+            Box::new(m20220101_000018_add_greetings::Migration),
+            Box::new(m20220101_000019_create_captcha_challenge::Migration),
+            Box::new(m20220101_000020_create_scheduled_message::Migration),
+        ]
     }
 }
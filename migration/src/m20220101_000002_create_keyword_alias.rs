@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeywordAlias::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeywordAlias::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(KeywordAlias::Alias).text().not_null())
+                    .col(ColumnDef::new(KeywordAlias::Keywords).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(KeywordAlias::GroupId)
+                            .col(KeywordAlias::Alias),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(KeywordAlias::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum KeywordAlias {
+    Table,
+    GroupId,
+    Alias,
+    Keywords,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+use crate::GroupReply;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupReply::Table)
+                    .add_column(
+                        ColumnDef::new(KeywordConditions::ExcludeWords)
+                            .text()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupReply::Table)
+                    .add_column(
+                        ColumnDef::new(KeywordConditions::RequireWords)
+                            .text()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupReply::Table)
+                    .drop_column(KeywordConditions::ExcludeWords)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupReply::Table)
+                    .drop_column(KeywordConditions::RequireWords)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum KeywordConditions {
+    ExcludeWords,
+    RequireWords,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GroupSubscription::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GroupSubscription::GroupId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GroupSubscription::SourceGroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GroupSubscription::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum GroupSubscription {
+    Table,
+    GroupId,
+    SourceGroupId,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GroupRole::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(GroupRole::GroupId).big_integer().not_null())
+                    .col(ColumnDef::new(GroupRole::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(GroupRole::Role).text().not_null())
+                    .col(ColumnDef::new(GroupRole::Username).text().null())
+                    .primary_key(
+                        Index::create()
+                            .col(GroupRole::GroupId)
+                            .col(GroupRole::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GroupRole::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum GroupRole {
+    Table,
+    GroupId,
+    UserId,
+    Role,
+    Username,
+}
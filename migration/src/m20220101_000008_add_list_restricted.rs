@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+use crate::GroupSetting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupSetting::Table)
+                    .add_column(
+                        ColumnDef::new(ListRestricted::ListRestricted)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupSetting::Table)
+                    .drop_column(ListRestricted::ListRestricted)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ListRestricted {
+    ListRestricted,
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeywordAudit::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeywordAudit::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(KeywordAudit::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(KeywordAudit::ActorId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(KeywordAudit::Action).text().not_null())
+                    .col(ColumnDef::new(KeywordAudit::Keywords).text().not_null())
+                    .col(ColumnDef::new(KeywordAudit::OldReply).text().null())
+                    .col(ColumnDef::new(KeywordAudit::NewReply).text().null())
+                    .col(
+                        ColumnDef::new(KeywordAudit::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_keyword_audit_group_id")
+                    .table(KeywordAudit::Table)
+                    .col(KeywordAudit::GroupId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(KeywordAudit::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum KeywordAudit {
+    Table,
+    Id,
+    GroupId,
+    ActorId,
+    Action,
+    Keywords,
+    OldReply,
+    NewReply,
+    CreatedAt,
+}
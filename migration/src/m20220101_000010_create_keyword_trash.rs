@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeywordTrash::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeywordTrash::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(KeywordTrash::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(KeywordTrash::Keywords).text().not_null())
+                    .col(ColumnDef::new(KeywordTrash::Reply).text().not_null())
+                    .col(ColumnDef::new(KeywordTrash::ExcludeWords).text().null())
+                    .col(ColumnDef::new(KeywordTrash::RequireWords).text().null())
+                    .col(ColumnDef::new(KeywordTrash::Aliases).text().null())
+                    .col(
+                        ColumnDef::new(KeywordTrash::OperationId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(KeywordTrash::DeletedBy)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(KeywordTrash::DeletedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_keyword_trash_group_id")
+                    .table(KeywordTrash::Table)
+                    .col(KeywordTrash::GroupId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(KeywordTrash::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum KeywordTrash {
+    Table,
+    Id,
+    GroupId,
+    Keywords,
+    Reply,
+    ExcludeWords,
+    RequireWords,
+    Aliases,
+    OperationId,
+    DeletedBy,
+    DeletedAt,
+}
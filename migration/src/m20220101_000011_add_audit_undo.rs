@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+use crate::KeywordAudit;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列，已有的记录不可撤销
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordAudit::Table)
+                    .add_column(
+                        ColumnDef::new(AuditUndo::OperationId)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordAudit::Table)
+                    .add_column(
+                        ColumnDef::new(AuditUndo::Undoable)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordAudit::Table)
+                    .add_column(ColumnDef::new(AuditUndo::OldKeywords).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordAudit::Table)
+                    .drop_column(AuditUndo::OperationId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordAudit::Table)
+                    .drop_column(AuditUndo::Undoable)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordAudit::Table)
+                    .drop_column(AuditUndo::OldKeywords)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum AuditUndo {
+    OperationId,
+    Undoable,
+    OldKeywords,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeywordStat::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeywordStat::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(KeywordStat::Keywords).text().not_null())
+                    .col(
+                        ColumnDef::new(KeywordStat::Hits)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(KeywordStat::LastHitAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(KeywordStat::GroupId)
+                            .col(KeywordStat::Keywords),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(KeywordStat::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum KeywordStat {
+    Table,
+    GroupId,
+    Keywords,
+    Hits,
+    LastHitAt,
+}
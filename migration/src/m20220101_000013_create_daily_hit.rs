@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DailyHit::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(DailyHit::GroupId).big_integer().not_null())
+                    .col(ColumnDef::new(DailyHit::Day).text().not_null())
+                    .col(
+                        ColumnDef::new(DailyHit::Hits)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .primary_key(Index::create().col(DailyHit::GroupId).col(DailyHit::Day))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DailyHit::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum DailyHit {
+    Table,
+    GroupId,
+    Day,
+    Hits,
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{GroupReply, GroupSetting, KeywordTrash};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupSetting::Table)
+                    .add_column(
+                        ColumnDef::new(Cooldowns::KeywordCooldownSecs)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupSetting::Table)
+                    .add_column(
+                        ColumnDef::new(Cooldowns::UserRateLimit)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupReply::Table)
+                    .add_column(ColumnDef::new(Cooldowns::CooldownSecs).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordTrash::Table)
+                    .add_column(ColumnDef::new(Cooldowns::CooldownSecs).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupSetting::Table)
+                    .drop_column(Cooldowns::KeywordCooldownSecs)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupSetting::Table)
+                    .drop_column(Cooldowns::UserRateLimit)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GroupReply::Table)
+                    .drop_column(Cooldowns::CooldownSecs)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(KeywordTrash::Table)
+                    .drop_column(Cooldowns::CooldownSecs)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Cooldowns {
+    KeywordCooldownSecs,
+    UserRateLimit,
+    CooldownSecs,
+}
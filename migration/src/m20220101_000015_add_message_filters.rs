@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use crate::GroupSetting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        for column in [
+            MessageFilters::IgnoreBots,
+            MessageFilters::IgnoreForwards,
+            MessageFilters::IgnoreChannelPosts,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .add_column(ColumnDef::new(column).boolean().not_null().default(false))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(IgnoredUser::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IgnoredUser::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IgnoredUser::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(IgnoredUser::Username).text().null())
+                    .primary_key(
+                        Index::create()
+                            .col(IgnoredUser::GroupId)
+                            .col(IgnoredUser::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IgnoredUser::Table).to_owned())
+            .await?;
+
+        for column in [
+            MessageFilters::IgnoreBots,
+            MessageFilters::IgnoreForwards,
+            MessageFilters::IgnoreChannelPosts,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+pub enum MessageFilters {
+    IgnoreBots,
+    IgnoreForwards,
+    IgnoreChannelPosts,
+}
+
+#[derive(DeriveIden)]
+pub enum IgnoredUser {
+    Table,
+    GroupId,
+    UserId,
+    Username,
+}
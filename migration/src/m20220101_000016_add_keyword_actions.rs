@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{GroupReply, KeywordTrash};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        for table in [
+            GroupReply::Table.into_iden(),
+            KeywordTrash::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table.clone())
+                        .add_column(ColumnDef::new(KeywordActions::Action).text().null())
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(
+                            ColumnDef::new(KeywordActions::ActionDurationSecs)
+                                .integer()
+                                .null(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            GroupReply::Table.into_iden(),
+            KeywordTrash::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table.clone())
+                        .drop_column(KeywordActions::Action)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(KeywordActions::ActionDurationSecs)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum KeywordActions {
+    Action,
+    ActionDurationSecs,
+}
@@ -0,0 +1,175 @@
+use sea_orm_migration::prelude::*;
+
+use crate::GroupSetting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        for (column, default) in [
+            (WarnThresholds::WarnMuteThreshold, 3),
+            (WarnThresholds::WarnBanThreshold, 5),
+            (WarnThresholds::WarnMuteSecs, 24 * 60 * 60),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .add_column(ColumnDef::new(column).integer().not_null().default(default))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserWarning::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserWarning::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserWarning::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UserWarning::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(UserWarning::Username).text().null())
+                    .col(ColumnDef::new(UserWarning::Reason).text().null())
+                    .col(
+                        ColumnDef::new(UserWarning::WarnedBy)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserWarning::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_warning_group_user")
+                    .table(UserWarning::Table)
+                    .col(UserWarning::GroupId)
+                    .col(UserWarning::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ModerationLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ModerationLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ModerationLog::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ModerationLog::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ModerationLog::Action).text().not_null())
+                    .col(ColumnDef::new(ModerationLog::Reason).text().null())
+                    .col(
+                        ColumnDef::new(ModerationLog::ActorId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ModerationLog::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_moderation_log_group_id")
+                    .table(ModerationLog::Table)
+                    .col(ModerationLog::GroupId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ModerationLog::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(UserWarning::Table).to_owned())
+            .await?;
+
+        for column in [
+            WarnThresholds::WarnMuteThreshold,
+            WarnThresholds::WarnBanThreshold,
+            WarnThresholds::WarnMuteSecs,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum WarnThresholds {
+    WarnMuteThreshold,
+    WarnBanThreshold,
+    WarnMuteSecs,
+}
+
+#[derive(DeriveIden)]
+pub enum UserWarning {
+    Table,
+    Id,
+    GroupId,
+    UserId,
+    Username,
+    Reason,
+    WarnedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum ModerationLog {
+    Table,
+    Id,
+    GroupId,
+    UserId,
+    Action,
+    Reason,
+    ActorId,
+    CreatedAt,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+use crate::GroupSetting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        for column in [Greetings::WelcomeMessage, Greetings::GoodbyeMessage] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .add_column(ColumnDef::new(column).text().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Greetings::WelcomeMessage, Greetings::GoodbyeMessage] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Greetings {
+    WelcomeMessage,
+    GoodbyeMessage,
+}
@@ -0,0 +1,110 @@
+use sea_orm_migration::prelude::*;
+
+use crate::GroupSetting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能添加一列
+        for column in [
+            ColumnDef::new(CaptchaSettings::CaptchaEnabled)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+            ColumnDef::new(CaptchaSettings::CaptchaMode)
+                .text()
+                .not_null()
+                .default("button")
+                .to_owned(),
+            ColumnDef::new(CaptchaSettings::CaptchaTimeoutSecs)
+                .integer()
+                .not_null()
+                .default(120)
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CaptchaChallenge::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CaptchaChallenge::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CaptchaChallenge::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CaptchaChallenge::MessageId).integer().null())
+                    .col(ColumnDef::new(CaptchaChallenge::Answer).text().not_null())
+                    .col(
+                        ColumnDef::new(CaptchaChallenge::ExpiresAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(CaptchaChallenge::GroupId)
+                            .col(CaptchaChallenge::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CaptchaChallenge::Table).to_owned())
+            .await?;
+
+        for column in [
+            CaptchaSettings::CaptchaEnabled,
+            CaptchaSettings::CaptchaMode,
+            CaptchaSettings::CaptchaTimeoutSecs,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GroupSetting::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CaptchaSettings {
+    CaptchaEnabled,
+    CaptchaMode,
+    CaptchaTimeoutSecs,
+}
+
+#[derive(DeriveIden)]
+pub enum CaptchaChallenge {
+    Table,
+    GroupId,
+    UserId,
+    MessageId,
+    Answer,
+    ExpiresAt,
+}
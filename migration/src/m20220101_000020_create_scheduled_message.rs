@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledMessage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduledMessage::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::GroupId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduledMessage::CronExpr).text().null())
+                    .col(
+                        ColumnDef::new(ScheduledMessage::IntervalSecs)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::OneShot)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(ScheduledMessage::Message).text().not_null())
+                    .col(
+                        ColumnDef::new(ScheduledMessage::Pin)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::AutoDelete)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::NextRunAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::CreatedBy)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_scheduled_message_next_run_at")
+                    .table(ScheduledMessage::Table)
+                    .col(ScheduledMessage::NextRunAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduledMessage::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ScheduledMessage {
+    Table,
+    Id,
+    GroupId,
+    CronExpr,
+    IntervalSecs,
+    OneShot,
+    Message,
+    Pin,
+    AutoDelete,
+    NextRunAt,
+    CreatedBy,
+    CreatedAt,
+}
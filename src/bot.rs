@@ -1,10 +1,28 @@
+use crate::config::Config;
 use crate::handlers::MessageHandler;
 use anyhow::Result;
 use frankenstein::AsyncTelegramApi;
 use frankenstein::client_reqwest::Bot;
 use frankenstein::methods::GetUpdatesParams;
+use frankenstein::types::AllowedUpdate;
 use frankenstein::updates::UpdateContent;
 use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+// 回收站和过期警告清理的执行间隔
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// 关键词命中次数写入数据库的间隔
+const STATS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+// 清理并保存冷却状态的间隔
+const COOLDOWN_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+// 检查入群验证是否超时的间隔
+const CAPTCHA_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// 检查定时消息是否到期的间隔
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 pub struct BotManager {
     api: Bot,
@@ -13,9 +31,9 @@ pub struct BotManager {
 }
 
 impl BotManager {
-    pub fn new(bot_token: &str, db: DatabaseConnection) -> Self {
-        let api = Bot::new(bot_token);
-        let message_handler = MessageHandler::new(db.clone());
+    pub fn new(config: &Config, db: DatabaseConnection) -> Self {
+        let api = Bot::new(&config.bot_token);
+        let message_handler = MessageHandler::new(db.clone(), config.clone());
 
         BotManager {
             api,
@@ -47,22 +65,139 @@ impl BotManager {
         }
     }
 
+    /// 启动定期执行的后台任务
+    fn spawn_background_tasks(&self) {
+        // 定期清除回收站中过期的关键词和过期的警告
+        let handler = self.message_handler.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TRASH_PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match handler.purge_expired_trash().await {
+                    Ok(0) => {}
+                    Ok(count) => println!("已清除回收站中 {} 个过期关键词", count),
+                    Err(e) => eprintln!("清除回收站时出错: {}", e),
+                }
+                match handler.purge_expired_warnings().await {
+                    Ok(0) => {}
+                    Ok(count) => println!("已清除 {} 条过期警告", count),
+                    Err(e) => eprintln!("清除过期警告时出错: {}", e),
+                }
+            }
+        });
+
+        // 定期将关键词的命中次数批量写入数据库
+        let handler = self.message_handler.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATS_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = handler.flush_keyword_hits().await {
+                    eprintln!("写入关键词统计时出错: {}", e);
+                }
+            }
+        });
+
+        // 定期清理过期的冷却状态，配置了状态文件时一并保存
+        let handler = self.message_handler.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(COOLDOWN_SAVE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = handler.save_cooldowns().await {
+                    eprintln!("保存冷却状态时出错: {}", e);
+                }
+            }
+        });
+
+        // 定期将超时未完成入群验证的成员移出群组
+        let handler = self.message_handler.clone();
+        let api = self.api.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CAPTCHA_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                match handler.expire_captchas(&api).await {
+                    Ok(0) => {}
+                    Ok(count) => println!("已移出 {} 名未完成入群验证的成员", count),
+                    Err(e) => eprintln!("处理入群验证超时时出错: {}", e),
+                }
+            }
+        });
+
+        // 定期发送到期的定时消息
+        let handler = self.message_handler.clone();
+        let api = self.api.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = handler.run_scheduled_messages(&api).await {
+                    eprintln!("发送定时消息时出错: {}", e);
+                }
+            }
+        });
+    }
+
     pub async fn start_listening(&self) -> Result<()> {
-        let mut update_params = GetUpdatesParams::builder().build();
+        self.spawn_background_tasks();
+
+        // chat_member 更新需要显式订阅，用于及时刷新管理员缓存
+        let mut update_params = GetUpdatesParams::builder()
+            .allowed_updates(vec![
+                AllowedUpdate::Message,
+                AllowedUpdate::CallbackQuery,
+                AllowedUpdate::InlineQuery,
+                AllowedUpdate::ChatMember,
+                AllowedUpdate::MyChatMember,
+            ])
+            .build();
 
         loop {
             match self.api.get_updates(&update_params).await {
                 Ok(response) => {
                     for update in response.result {
-                        if let UpdateContent::Message(message) = update.content {
-                            let api_clone = self.api.clone();
-                            let handler = self.message_handler.clone();
-
-                            tokio::spawn(async move {
-                                if let Err(e) = handler.handle_message(api_clone, *message).await {
-                                    eprintln!("处理消息时出错: {}", e);
-                                }
-                            });
+                        let api_clone = self.api.clone();
+                        let handler = self.message_handler.clone();
+
+                        match update.content {
+                            UpdateContent::Message(message) => {
+                                tokio::spawn(async move {
+                                    if let Err(e) =
+                                        handler.handle_message(api_clone, *message).await
+                                    {
+                                        eprintln!("处理消息时出错: {}", e);
+                                    }
+                                });
+                            }
+                            UpdateContent::CallbackQuery(query) => {
+                                tokio::spawn(async move {
+                                    if let Err(e) =
+                                        handler.handle_callback_query(api_clone, *query).await
+                                    {
+                                        eprintln!("处理回调查询时出错: {}", e);
+                                    }
+                                });
+                            }
+                            UpdateContent::InlineQuery(query) => {
+                                tokio::spawn(async move {
+                                    if let Err(e) =
+                                        handler.handle_inline_query(api_clone, query).await
+                                    {
+                                        eprintln!("处理内联查询时出错: {}", e);
+                                    }
+                                });
+                            }
+                            UpdateContent::ChatMember(update)
+                            | UpdateContent::MyChatMember(update) => {
+                                tokio::spawn(async move {
+                                    if let Err(e) = handler.handle_chat_member_update(update).await
+                                    {
+                                        eprintln!("处理成员变更时出错: {}", e);
+                                    }
+                                });
+                            }
+                            _ => {}
                         }
                         update_params.offset = Some(i64::from(update.update_id) + 1);
                     }
@@ -3,11 +3,61 @@ use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
+use std::str::FromStr;
+
+/// 管理员执行修改类命令时必须具备的管理权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminRight {
+    ManageChat,
+    ChangeInfo,
+    DeleteMessages,
+    RestrictMembers,
+    InviteUsers,
+    PinMessages,
+    PromoteMembers,
+}
+
+impl AdminRight {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdminRight::ManageChat => "can_manage_chat",
+            AdminRight::ChangeInfo => "can_change_info",
+            AdminRight::DeleteMessages => "can_delete_messages",
+            AdminRight::RestrictMembers => "can_restrict_members",
+            AdminRight::InviteUsers => "can_invite_users",
+            AdminRight::PinMessages => "can_pin_messages",
+            AdminRight::PromoteMembers => "can_promote_members",
+        }
+    }
+}
+
+impl FromStr for AdminRight {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "can_manage_chat" => Ok(AdminRight::ManageChat),
+            "can_change_info" => Ok(AdminRight::ChangeInfo),
+            "can_delete_messages" => Ok(AdminRight::DeleteMessages),
+            "can_restrict_members" => Ok(AdminRight::RestrictMembers),
+            "can_invite_users" => Ok(AdminRight::InviteUsers),
+            "can_pin_messages" => Ok(AdminRight::PinMessages),
+            "can_promote_members" => Ok(AdminRight::PromoteMembers),
+            _ => Err(anyhow::anyhow!("未知的管理员权限: {}", s)),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub bot_token: String,
     pub database_url: String,
+    pub owner_id: Option<u64>,
+    pub inline_group_ids: Vec<i64>,
+    pub admin_required_right: Option<AdminRight>,
+    pub trash_retention_days: u64,
+    pub cooldown_state_file: Option<String>,
+    pub warn_expire_days: u64,
 }
 
 impl Config {
@@ -76,9 +126,75 @@ impl Config {
         let database_url =
             env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:keywords_reply_bot.db".to_string());
 
+        // 机器人所有者的用户 ID，用于管理全局关键词，未设置时无人可以管理
+        let owner_id = match env::var("OWNER_ID") {
+            Ok(value) => Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("OWNER_ID 格式错误: {}", e))?,
+            ),
+            Err(_) => None,
+        };
+
+        // 内联查询时始终可以搜索的群组，多个 ID 用逗号分隔
+        let inline_group_ids = match env::var("INLINE_GROUPS") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| {
+                    id.parse()
+                        .map_err(|e| anyhow::anyhow!("INLINE_GROUPS 格式错误: {}", e))
+                })
+                .collect::<Result<Vec<i64>>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        // 管理员必须具备该权限才能修改关键词，未设置时任何管理员都可以
+        let admin_required_right = match env::var("ADMIN_REQUIRED_RIGHT") {
+            Ok(value) => Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("ADMIN_REQUIRED_RIGHT 格式错误: {}", e))?,
+            ),
+            Err(_) => None,
+        };
+
+        // 删除的关键词在回收站中保留的天数
+        let trash_retention_days = match env::var("TRASH_RETENTION_DAYS") {
+            Ok(value) => value
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("TRASH_RETENTION_DAYS 格式错误: {}", e))?,
+            Err(_) => 30,
+        };
+
+        // 冷却状态的保存文件，未设置时冷却状态只保存在内存中，重启后清空
+        let cooldown_state_file = env::var("COOLDOWN_STATE_FILE")
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty());
+
+        // 警告的有效天数，过期的警告不再计入升级阈值，0 表示永不过期
+        let warn_expire_days = match env::var("WARN_EXPIRE_DAYS") {
+            Ok(value) => value
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("WARN_EXPIRE_DAYS 格式错误: {}", e))?,
+            Err(_) => 30,
+        };
+
         Ok(Config {
             bot_token,
             database_url,
+            owner_id,
+            inline_group_ids,
+            admin_required_right,
+            trash_retention_days,
+            cooldown_state_file,
+            warn_expire_days,
         })
     }
 
@@ -1,22 +1,411 @@
-use sea_orm::entity::prelude::*;
-use serde::{Deserialize, Serialize};
+// 关键词回复实体
+pub mod group_reply {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "group_reply")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub keywords: String,
+        pub reply: String,
+        // 排除词，消息包含其中任意一个时不触发，多个用 | 分隔
+        pub exclude_words: Option<String>,
+        // 附加条件，消息必须同时包含其中所有词才触发，多个用 | 分隔
+        pub require_words: Option<String>,
+        // 本关键词的冷却秒数，为空时使用群组设置
+        pub cooldown_secs: Option<i32>,
+        // 触发后执行的管理动作：delete、warn、mute 或 ban，为空时只回复
+        pub action: Option<String>,
+        // mute 和 ban 的持续秒数，为空时永久生效
+        pub action_duration_secs: Option<i32>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
-#[sea_orm(table_name = "group_reply")]
-pub struct Model {
-    #[sea_orm(primary_key, auto_increment = false)]
-    pub group_id: i64,
-    #[sea_orm(primary_key, auto_increment = false)]
-    pub keywords: String,
-    pub reply: String,
+    impl ActiveModelBehavior for ActiveModel {}
 }
 
-#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+// 关键词别名实体，每个别名指向同一群组中的一条主关键词
+pub mod keyword_alias {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
 
-impl ActiveModelBehavior for ActiveModel {}
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "keyword_alias")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub alias: String,
+        pub keywords: String,
+    }
 
-// 导出实体模块
-pub mod group_reply {
-    pub use super::*;
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 群组设置实体，未设置过的群组没有对应记录，按默认值处理
+pub mod group_setting {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "group_setting")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        // 是否关闭全局关键词
+        pub global_disabled: bool,
+        // 是否仅允许拥有角色的用户查看关键词列表
+        pub list_restricted: bool,
+        // 同一关键词在本群两次回复之间的最小间隔秒数，0 表示不限制
+        pub keyword_cooldown_secs: i32,
+        // 每个用户每分钟最多触发的回复次数，0 表示不限制
+        pub user_rate_limit: i32,
+        // 是否忽略其他机器人发送的消息
+        pub ignore_bots: bool,
+        // 是否忽略转发的消息
+        pub ignore_forwards: bool,
+        // 是否忽略关联频道自动转发到讨论组的消息
+        pub ignore_channel_posts: bool,
+        // 有效警告达到该次数时禁言，0 表示不禁言
+        pub warn_mute_threshold: i32,
+        // 有效警告达到该次数时封禁，0 表示不封禁
+        pub warn_ban_threshold: i32,
+        // 警告升级为禁言时的禁言秒数
+        pub warn_mute_secs: i32,
+        // 新成员加入时发送的欢迎消息模板，为空表示不发送
+        pub welcome_message: Option<String>,
+        // 成员离开时发送的告别消息模板，为空表示不发送
+        pub goodbye_message: Option<String>,
+        // 是否要求新成员完成入群验证
+        pub captcha_enabled: bool,
+        // 入群验证的方式，button 为点击按钮，math 为算术题
+        pub captcha_mode: String,
+        // 新成员完成验证的时限秒数，超时未完成则移出群组
+        pub captcha_timeout_secs: i32,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 群组订阅实体，匹配时继承来源群组的关键词
+pub mod group_subscription {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "group_subscription")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        pub source_group_id: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 私聊管理会话实体，记录每个用户在私聊中当前管理的群组
+pub mod user_session {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "user_session")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+        pub group_id: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 群组角色实体，为非管理员用户授予关键词管理权限
+pub mod group_role {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "group_role")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+        // owner、editor 或 viewer
+        pub role: String,
+        // 授权时记录的用户名，便于显示和按用户名撤销
+        pub username: Option<String>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 忽略用户实体，列表中的用户发送的消息不会触发关键词回复
+pub mod ignored_user {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "ignored_user")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+        // 加入列表时记录的用户名，便于显示和按用户名移除
+        pub username: Option<String>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 关键词变更记录实体，记录每次添加、修改和删除前后的回复内容
+pub mod keyword_audit {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "keyword_audit")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub group_id: i64,
+        // 操作者的用户 ID，匿名管理员为群组 ID
+        pub actor_id: i64,
+        // add、update、delete、delete_all、rename 或 restore
+        pub action: String,
+        pub keywords: String,
+        pub old_reply: Option<String>,
+        pub new_reply: Option<String>,
+        // Unix 时间戳（秒）
+        pub created_at: i64,
+        // 同一次操作产生的记录共用一个 ID，/undo 按操作整体撤销
+        pub operation_id: i64,
+        // 已撤销的记录和撤销时产生的记录不可再撤销
+        pub undoable: bool,
+        // 重命名前的关键词
+        pub old_keywords: Option<String>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 回收站实体，删除的关键词连同别名和触发条件保留一段时间后再清除
+pub mod keyword_trash {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "keyword_trash")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub group_id: i64,
+        pub keywords: String,
+        pub reply: String,
+        pub exclude_words: Option<String>,
+        pub require_words: Option<String>,
+        // 删除时的别名，多个用 | 分隔
+        pub aliases: Option<String>,
+        pub cooldown_secs: Option<i32>,
+        pub action: Option<String>,
+        pub action_duration_secs: Option<i32>,
+        // 删除该关键词的操作，与变更记录中的 operation_id 对应
+        pub operation_id: i64,
+        pub deleted_by: i64,
+        // Unix 时间戳（秒）
+        pub deleted_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 关键词命中统计实体，按触发所在的群组分别计数
+pub mod keyword_stat {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "keyword_stat")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub keywords: String,
+        pub hits: i64,
+        // 最近一次触发的 Unix 时间戳（秒）
+        pub last_hit_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 群组每日触发次数实体
+pub mod daily_hit {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "daily_hit")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        // 本地日期，格式为 YYYY-MM-DD
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub day: String,
+        pub hits: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 用户警告实体，过期的警告不再计入升级阈值
+pub mod user_warning {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "user_warning")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub group_id: i64,
+        pub user_id: i64,
+        // 警告时记录的用户名，便于显示
+        pub username: Option<String>,
+        pub reason: Option<String>,
+        // 发出警告的管理员 ID，由关键词动作发出时为 0
+        pub warned_by: i64,
+        // Unix 时间戳（秒）
+        pub created_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 管理操作记录实体，记录警告、禁言、封禁等每一次管理动作
+pub mod moderation_log {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "moderation_log")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub group_id: i64,
+        // 被处理的用户 ID
+        pub user_id: i64,
+        // delete、warn、mute、ban 或 reset_warns
+        pub action: String,
+        pub reason: Option<String>,
+        // 操作者的用户 ID，由关键词或警告升级自动执行时为 0
+        pub actor_id: i64,
+        // Unix 时间戳（秒）
+        pub created_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 入群验证实体，记录尚未完成验证的新成员，重启后继续计时
+pub mod captcha_challenge {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "captcha_challenge")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub group_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+        // 验证消息的 ID，验证结束后删除该消息
+        pub message_id: Option<i32>,
+        // 正确答案，按钮验证时为固定值
+        pub answer: String,
+        // Unix 时间戳（秒）
+        pub expires_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+// 定时消息实体，按 cron 表达式或固定间隔向群组发送消息
+pub mod scheduled_message {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+    #[sea_orm(table_name = "scheduled_message")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub group_id: i64,
+        // 5 段的 cron 表达式，与 interval_secs 二选一
+        pub cron_expr: Option<String>,
+        // 固定的发送间隔秒数
+        pub interval_secs: Option<i32>,
+        // 是否只发送一次，发送后删除
+        pub one_shot: bool,
+        pub message: String,
+        // 发送后是否置顶
+        pub pin: bool,
+        // 发送后是否与其他回复一样自动删除
+        pub auto_delete: bool,
+        // 下次发送的 Unix 时间戳（秒）
+        pub next_run_at: i64,
+        pub created_by: i64,
+        // Unix 时间戳（秒）
+        pub created_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
 }
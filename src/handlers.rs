@@ -1,26 +1,116 @@
+use crate::config::Config;
 use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::entities::group_setting::Entity as GroupSettingEntity;
+use crate::entities::group_subscription::Entity as GroupSubscriptionEntity;
+use crate::entities::keyword_alias::{self, Entity as KeywordAliasEntity};
+use crate::matcher;
+use admin_cache::AdminCache;
 use anyhow::Result;
+use audit::{AuditAction, AuditEntry, Operation};
+use confirm::{PendingAction, PendingConfirmations};
+use cooldown::Cooldowns;
 use frankenstein::AsyncTelegramApi;
 use frankenstein::ParseMode;
 use frankenstein::client_reqwest::Bot;
-use frankenstein::methods::{DeleteMessageParams, GetChatMemberParams, SendMessageParams};
-use frankenstein::types::{ChatMember, Message, MessageEntityType};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use frankenstein::methods::{
+    AnswerCallbackQueryParams, DeleteMessageParams, GetChatMemberParams, SendMessageParams,
+};
+use frankenstein::types::{
+    CallbackQuery, ChatMember, InlineKeyboardMarkup, Message, MessageEntityType, ReplyMarkup,
+};
+use inline::InlineGroupCache;
+use moderation::RightNotices;
+use permission::Permission;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, Set, TransactionTrait,
+};
+use stats::HitCounter;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::OnceCell;
+use welcome::Greeting;
+
+mod admin_cache;
+mod audit;
+mod captcha;
+mod confirm;
+mod cooldown;
+mod filter;
+mod global;
+mod inline;
+mod inspect;
+mod keyword_list;
+mod moderation;
+mod permission;
+mod placeholder;
+mod schedule;
+mod session;
+mod settings;
+mod stats;
+mod sync;
+mod transfer;
+mod trash;
+mod undo;
+mod warning;
+mod welcome;
 
 const AUTO_DELETE_AFTER_SECS: u64 = 40;
 
+/// 全局关键词使用的群组 ID，Telegram 的会话 ID 不会为 0
+pub const GLOBAL_GROUP_ID: i64 = 0;
+
+#[derive(Clone, Copy)]
+enum ConditionKind {
+    Exclude,
+    Require,
+}
+
+enum RenameResult {
+    Renamed,
+    NotFound,
+    Conflict,
+}
+
 #[derive(Clone)]
 pub struct MessageHandler {
     db: DatabaseConnection,
+    config: Config,
+    // 机器人自身的用户 ID，首次使用时通过 get_me 获取
+    bot_id: Arc<OnceCell<u64>>,
+    inline_groups: InlineGroupCache,
+    admin_cache: AdminCache,
+    confirmations: PendingConfirmations,
+    hit_counter: HitCounter,
+    cooldowns: Cooldowns,
+    right_notices: RightNotices,
 }
 
 impl MessageHandler {
-    pub fn new(db: DatabaseConnection) -> Self {
-        MessageHandler { db }
+    pub fn new(db: DatabaseConnection, config: Config) -> Self {
+        let handler = MessageHandler {
+            db,
+            config,
+            bot_id: Arc::default(),
+            inline_groups: InlineGroupCache::default(),
+            admin_cache: AdminCache::default(),
+            confirmations: PendingConfirmations::default(),
+            hit_counter: HitCounter::default(),
+            cooldowns: Cooldowns::default(),
+            right_notices: RightNotices::default(),
+        };
+        handler.load_cooldowns();
+        handler
     }
 
     pub async fn handle_message(&self, api: Bot, message: Message) -> Result<()> {
+        // 成员加入和离开的服务消息没有文本，单独处理
+        if message.new_chat_members.is_some() || message.left_chat_member.is_some() {
+            return self.handle_member_service_message(api, &message).await;
+        }
+
         if let Some(text) = &message.text {
             println!("收到消息: {}", text);
 
@@ -36,181 +126,319 @@ impl MessageHandler {
         Ok(())
     }
 
+    pub async fn handle_callback_query(&self, api: Bot, query: CallbackQuery) -> Result<()> {
+        let data = query.data.clone().unwrap_or_default();
+        let (kind, args) = data.split_once(':').unwrap_or((data.as_str(), ""));
+
+        match kind {
+            keyword_list::CALLBACK_PREFIX => {
+                self.handle_keyword_list_callback(&api, &query, args)
+                    .await?;
+            }
+            session::CALLBACK_PREFIX => {
+                self.handle_session_callback(&api, &query, args).await?;
+            }
+            captcha::CALLBACK_PREFIX => {
+                self.handle_captcha_callback(&api, &query, args).await?;
+            }
+            confirm::CALLBACK_PREFIX => {
+                self.handle_confirm_callback(&api, &query, args).await?;
+            }
+            _ => {
+                // 未知回调，仅结束按钮的加载状态
+                self.answer_callback(&api, &query, None).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     async fn handle_command(&self, api: Bot, message: &Message, _text: &str) -> Result<()> {
         // 通过实体 offset 来判断命令类型
-        if let Some(command) = self.get_bot_command_from_entities(message)? {
-            match command.as_str() {
-                "/add" => {
-                    // 通过实体 offset 获取命令后的内容
-                    let content = self.get_content_after_command(message, &command)?;
-
-                    if content.is_empty() {
-                        self.send_reply(
-                            api,
-                            message.chat.id,
-                            "用法: /add &lt;关键词&gt; &lt;回复内容&gt;",
-                            None,
-                        )
-                        .await?;
-                        return Ok(());
-                    }
-
-                    // 找到第一个空格或换行符的位置
-                    let delimiter_pos = content.find(|c| c == ' ' || c == '\n');
-                    if let Some(pos) = delimiter_pos {
-                        let keywords = content[..pos].to_string();
-                        let reply_content = content[pos + 1..].to_string();
-
-                        if keywords.is_empty() || reply_content.is_empty() {
-                            self.send_reply(
-                                api,
-                                message.chat.id,
-                                "用法: /add &lt;关键词&gt; &lt;回复内容&gt;",
-                                None,
-                            )
-                            .await?;
-                            return Ok(());
-                        }
+        let Some(command) = self.get_bot_command_from_entities(message)? else {
+            // 没有找到命令实体，不进行回应
+            return Ok(());
+        };
 
-                        // 检查用户是否为管理员
-                        if !self.is_admin(&api, message).await? {
-                            self.send_reply(api, message.chat.id, "只有管理员才能使用此命令", None)
-                                .await?;
-                            return Ok(());
-                        }
+        // 不依赖目标群组的命令
+        match command.as_str() {
+            "/start" => {
+                self.handle_start_command(api, message, &command).await?;
+                return Ok(());
+            }
+            "/manage" => {
+                self.handle_manage_command(api, message).await?;
+                return Ok(());
+            }
+            "/groups" => {
+                self.handle_groups_command(api, message).await?;
+                return Ok(());
+            }
+            "/use" => {
+                self.handle_use_command(api, message, &command).await?;
+                return Ok(());
+            }
+            "/help" => {
+                self.send_reply(api, message.chat.id, "可用命令:\n/add &lt;关键词&gt; &lt;回复内容&gt; - 添加关键词回复，多个触发词用 | 分隔（编辑者）\n/del &lt;关键词&gt; - 删除关键词回复及其别名（编辑者）\n/alias &lt;关键词&gt; &lt;别名1|别名2...&gt; - 为关键词添加别名（编辑者）\n/unalias &lt;别名&gt; - 删除别名（编辑者）\n/edit &lt;关键词&gt; &lt;新回复内容&gt; - 修改已有关键词的回复（编辑者）\n/append &lt;关键词&gt; &lt;追加内容&gt; - 在回复末尾追加内容（编辑者）\n/rename &lt;原关键词&gt; &lt;新关键词&gt; - 重命名关键词，保留别名和条件（编辑者）\n/exclude &lt;关键词&gt; [词1|词2...] - 设置排除词，省略则清除（编辑者）\n/require &lt;关键词&gt; [词1|词2...] - 设置必须同时包含的词，省略则清除（编辑者）\n/del_all - 删除当前群组的所有关键词，需确认（仅群组管理员）\n/history [关键词] - 查看关键词的变更记录（编辑者）\n/stats - 查看常用关键词、从未触发的关键词和每日触发次数（编辑者）\n/undo - 撤销你在本群最近一次对关键词的修改（编辑者）\n/trash - 查看回收站中已删除的关键词（编辑者）\n/restore &lt;关键词&gt; - 从回收站恢复关键词及其别名（编辑者）\n/warn &lt;@用户名|用户ID&gt; [原因] - 警告用户，达到阈值自动禁言或封禁，也可回复用户消息使用（仅群组管理员）\n/warns [@用户名|用户ID] - 查看用户的有效警告，省略则列出所有被警告的用户（仅群组管理员）\n/resetwarns &lt;@用户名|用户ID&gt; - 清除用户的警告（仅群组管理员）\n/warnconfig [mute &lt;次数&gt; [时长]|ban &lt;次数&gt;] - 查看或设置警告的禁言和封禁阈值（仅群组管理员）\n/modlog - 查看最近的管理操作记录（仅群组管理员）\n/action &lt;关键词&gt; [delete|warn|mute [时长]|ban [时长]|none] - 设置关键词触发后删除消息、警告、禁言或封禁发送者（仅群组管理员）\n/cooldown [关键词] [秒数|default] - 查看或设置本群或单个关键词的冷却时间（编辑者）\n/ratelimit [次数] - 查看或设置每人每分钟最多触发的回复次数，0 表示不限制（仅群组管理员）\n/all [name] - 分页查看当前群组的所有关键词，name 表示按名称排序\n/get &lt;关键词&gt; - 查看关键词的原始回复内容和设置\n/search &lt;搜索词&gt; - 模糊搜索关键词和回复内容\n/test &lt;示例文本&gt; - 预览该文本会触发哪些关键词，不会真正回复（编辑者）\n/export [json|csv] - 导出当前群组的关键词（编辑者）\n/import [merge|replace] - 回复导出文件以导入关键词，默认合并（编辑者）\n/copyfrom &lt;群组ID&gt; [merge|replace] - 从另一个群组复制关键词，需同时是两个群组的编辑者\n/subscribe &lt;群组ID&gt; - 实时继承另一个群组的关键词，本群同名关键词优先（编辑者）\n/unsubscribe - 取消继承（编辑者）\n/global on|off - 启用或关闭全局关键词（仅管理员）\n/grant &lt;@用户名|用户ID&gt; owner|editor|viewer - 授予角色，也可回复用户消息使用（仅管理员）\n/revoke &lt;@用户名|用户ID&gt; - 撤销角色，也可回复用户消息使用（仅管理员）\n/roles - 查看本群已授予的角色（仅管理员）\n/reload - 立即刷新管理员列表缓存（仅管理员）\n/restrict_list on|off - 开启后仅拥有角色的用户可查看关键词（仅管理员）\n/filter [bots|forwards|channel on|off] - 查看或设置是否忽略机器人、转发和关联频道的消息（仅群组管理员）\n/ignore [@用户名|用户ID] - 忽略用户的消息，省略则查看忽略列表，也可回复用户消息使用（仅群组管理员）\n/unignore &lt;@用户名|用户ID&gt; - 取消忽略用户，也可回复用户消息使用（仅群组管理员）\n/setwelcome [内容] - 设置新成员加入时的欢迎消息，支持 {name} {mention} 等占位符（仅群组管理员）\n/setgoodbye [内容] - 设置成员离开时的告别消息（仅群组管理员）\n/welcome off - 关闭欢迎消息，/goodbye off 关闭告别消息（仅群组管理员）\n/captcha [on|off|button|math|timeout &lt;时长&gt;] - 查看或设置入群验证，新成员须在时限内点击按钮或答对算术题，否则移出群组（仅群组管理员）\n/schedule [once] &lt;间隔|\"cron 表达式\"&gt; [--pin] [--delete] &lt;内容&gt; - 定时或周期性地向本群发送消息（仅群组管理员）\n/schedules - 查看本群的定时消息（仅群组管理员）\n/unschedule &lt;ID&gt; - 删除定时消息（仅群组管理员）\n/gadd &lt;关键词&gt; &lt;回复内容&gt; - 添加全局关键词（仅机器人所有者）\n/gdel &lt;关键词&gt; - 删除全局关键词（仅机器人所有者）\n/gall - 查看全局关键词（仅机器人所有者）\n/manage - 在群组中获取私聊管理入口（编辑者）\n/groups - 在私聊中选择要管理的群组\n/use [群组ID] - 在私聊中切换或查看当前管理的群组\n/help - 显示帮助信息", None).await?;
+                return Ok(());
+            }
+            _ => {}
+        }
 
-                        // 处理回复内容，检查消息实体中的 code 类型并用 <code> 标签包裹
-                        let processed_reply = self
-                            .process_reply_with_entities(&reply_content, &message)
-                            .await?;
-
-                        // 保存到数据库
-                        let is_updated = self
-                            .add_keyword_reply(message.chat.id, keywords.clone(), processed_reply)
-                            .await?;
-                        let message_text = if is_updated {
-                            format!("关键词 <code>{}</code> 的回复内容已更新成功！", keywords)
-                        } else {
-                            format!("关键词 <code>{}</code> 回复已添加成功！", keywords)
-                        };
-                        self.send_reply(api, message.chat.id, &message_text, None)
-                            .await?;
-                    } else {
-                        self.send_reply(
-                            api,
-                            message.chat.id,
-                            "用法: /add &lt;关键词&gt; &lt;回复内容&gt;",
-                            None,
-                        )
-                        .await?;
-                        return Ok(());
-                    }
+        // 全局关键词命令操作全局关键词；其余命令在群组中操作当前群组，
+        // 在私聊中操作通过 /manage 或 /groups 选择的群组
+        let group_id = if matches!(command.as_str(), "/gadd" | "/gdel" | "/gall") {
+            GLOBAL_GROUP_ID
+        } else {
+            let Some(group_id) = self.resolve_target_group(message).await? else {
+                if self.is_private_chat(message) {
+                    self.send_reply(
+                        api,
+                        message.chat.id,
+                        "请先在群组中发送 /manage，或使用 /groups 选择要管理的群组",
+                        None,
+                    )
+                    .await?;
                 }
-                "/del" => {
-                    let content = self.get_content_after_command(message, &command)?;
-                    if content.is_empty() {
-                        self.send_reply(api, message.chat.id, "用法: /del &lt;关键词&gt;", None)
-                            .await?;
-                        return Ok(());
-                    }
+                return Ok(());
+            };
+            group_id
+        };
 
-                    // 检查用户是否为管理员
-                    if !self.is_admin(&api, message).await? {
-                        self.send_reply(api, message.chat.id, "只有管理员才能使用此命令", None)
-                            .await?;
-                        return Ok(());
-                    }
+        // /reload 需要先丢弃缓存，刚被任命的管理员才能通过权限检查
+        if command == "/reload" {
+            self.invalidate_admin_cache(group_id);
+        }
 
-                    let keywords = content.trim().to_string();
-
-                    // 删除关键词
-                    match self
-                        .delete_keyword_reply(message.chat.id, keywords.clone())
-                        .await
-                    {
-                        Ok(true) => {
-                            self.send_reply(
-                                api,
-                                message.chat.id,
-                                &format!("关键词 <code>{}</code> 已删除成功！", keywords),
-                                None,
-                            )
-                            .await?;
-                        }
-                        Ok(false) => {
-                            self.send_reply(
-                                api,
-                                message.chat.id,
-                                &format!("未找到关键词 <code>{}</code>", keywords),
-                                None,
-                            )
-                            .await?;
-                        }
-                        Err(e) => {
-                            eprintln!("删除关键词时出错: {}", e);
-                            self.send_reply(
-                                api,
-                                message.chat.id,
-                                "删除关键词时出错，请稍后重试",
-                                None,
-                            )
-                            .await?;
-                        }
-                    }
-                }
-                "/all" => {
-                    self.show_all_keywords(api, message.chat.id).await?;
-                }
-                "/del_all" => {
-                    // 检查用户是否为管理员
-                    if !self.is_admin(&api, message).await? {
-                        self.send_reply(api, message.chat.id, "只有管理员才能使用此命令", None)
-                            .await?;
-                        return Ok(());
-                    }
+        // 所有命令统一按权限策略检查，未知命令不进行回应
+        if let Some(permission) = Permission::for_command(&command)
+            && !self
+                .check_permission(&api, message, group_id, permission)
+                .await?
+        {
+            return Ok(());
+        }
 
-                    // 删除所有关键词
-                    match self.delete_all_keywords(message.chat.id).await {
-                        Ok(count) => {
-                            if count > 0 {
-                                self.send_reply(
-                                    api,
-                                    message.chat.id,
-                                    &format!("已删除 {} 个关键词！", count),
-                                    None,
-                                )
-                                .await?;
-                            } else {
-                                self.send_reply(
-                                    api,
-                                    message.chat.id,
-                                    "当前群组没有设置任何关键词",
-                                    None,
-                                )
-                                .await?;
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("删除所有关键词时出错: {}", e);
-                            self.send_reply(
-                                api,
-                                message.chat.id,
-                                "删除关键词时出错，请稍后重试",
-                                None,
-                            )
-                            .await?;
-                        }
-                    }
-                }
-                "/help" => {
-                    self.send_reply(api, message.chat.id, "可用命令:\n/add &lt;关键词&gt; &lt;回复内容&gt; - 添加关键词回复（仅管理员）\n/del &lt;关键词&gt; - 删除关键词回复（仅管理员）\n/del_all - 删除当前群组的所有关键词（仅管理员）\n/all - 查看当前群组的所有关键词\n/help - 显示帮助信息", None).await?;
-                }
-                _ => {
-                    // 未知命令，不进行回应
-                }
+        match command.as_str() {
+            "/add" | "/gadd" => {
+                self.handle_add_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/del" | "/gdel" => {
+                self.handle_del_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/gall" => {
+                self.handle_gall_command(api, message).await?;
+            }
+            "/grant" => {
+                self.handle_grant_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/revoke" => {
+                self.handle_revoke_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/roles" => {
+                self.handle_roles_command(api, message, group_id).await?;
+            }
+            "/reload" => {
+                self.handle_reload_command(api, message, group_id).await?;
+            }
+            "/history" => {
+                self.handle_history_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/stats" => {
+                self.handle_stats_command(api, message, group_id).await?;
+            }
+            "/undo" => {
+                self.handle_undo_command(api, message, group_id).await?;
+            }
+            "/trash" => {
+                self.handle_trash_command(api, message, group_id).await?;
+            }
+            "/restore" => {
+                self.handle_restore_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/cooldown" => {
+                self.handle_cooldown_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/ratelimit" => {
+                self.handle_ratelimit_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/warn" => {
+                self.handle_warn_command(api, message, group_id).await?;
+            }
+            "/warns" => {
+                self.handle_warns_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/resetwarns" => {
+                self.handle_resetwarns_command(api, message, group_id)
+                    .await?;
+            }
+            "/warnconfig" => {
+                self.handle_warnconfig_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/modlog" => {
+                self.handle_modlog_command(api, message, group_id).await?;
+            }
+            "/action" => {
+                self.handle_action_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/filter" => {
+                self.handle_filter_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/ignore" => {
+                self.handle_ignore_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/unignore" => {
+                self.handle_unignore_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/setwelcome" => {
+                self.handle_set_greeting_command(
+                    api,
+                    message,
+                    &command,
+                    group_id,
+                    Greeting::Welcome,
+                )
+                .await?;
+            }
+            "/setgoodbye" => {
+                self.handle_set_greeting_command(
+                    api,
+                    message,
+                    &command,
+                    group_id,
+                    Greeting::Goodbye,
+                )
+                .await?;
+            }
+            "/welcome" => {
+                self.handle_greeting_command(api, message, &command, group_id, Greeting::Welcome)
+                    .await?;
+            }
+            "/goodbye" => {
+                self.handle_greeting_command(api, message, &command, group_id, Greeting::Goodbye)
+                    .await?;
+            }
+            "/schedule" => {
+                self.handle_schedule_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/schedules" => {
+                self.handle_schedules_command(api, message, group_id)
+                    .await?;
+            }
+            "/unschedule" => {
+                self.handle_unschedule_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/captcha" => {
+                self.handle_captcha_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/restrict_list" => {
+                self.handle_restrict_list_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/global" => {
+                self.handle_global_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/alias" => {
+                self.handle_alias_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/unalias" => {
+                self.handle_unalias_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/edit" | "/append" => {
+                self.handle_edit_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/rename" => {
+                self.handle_rename_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/get" => {
+                self.handle_get_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/search" => {
+                self.handle_search_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/test" => {
+                self.handle_test_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/export" => {
+                self.handle_export_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/import" => {
+                self.handle_import_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/copyfrom" => {
+                self.handle_copyfrom_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/subscribe" => {
+                self.handle_subscribe_command(api, message, &command, group_id)
+                    .await?;
+            }
+            "/unsubscribe" => {
+                self.handle_unsubscribe_command(api, message, group_id)
+                    .await?;
+            }
+            "/exclude" => {
+                self.handle_condition_command(
+                    api,
+                    message,
+                    &command,
+                    group_id,
+                    ConditionKind::Exclude,
+                )
+                .await?;
+            }
+            "/require" => {
+                self.handle_condition_command(
+                    api,
+                    message,
+                    &command,
+                    group_id,
+                    ConditionKind::Require,
+                )
+                .await?;
+            }
+            "/all" => {
+                let sort = self
+                    .get_content_after_command(message, &command)?
+                    .trim()
+                    .parse()
+                    .unwrap_or_default();
+                self.show_all_keywords(api, message.chat.id, group_id, sort)
+                    .await?;
+            }
+            "/del_all" => {
+                self.handle_del_all_command(api, message, group_id).await?;
+            }
+            _ => {
+                // 未知命令，不进行回应
             }
-        } else {
-            // 没有找到命令实体，不进行回应
         }
 
         Ok(())
@@ -218,56 +446,71 @@ impl MessageHandler {
 
     async fn handle_keyword_reply(&self, api: Bot, message: &Message, text: &str) -> Result<()> {
         // 查询数据库中的关键词匹配
-        let replies = GroupReplyEntity::find()
-            .filter(group_reply::Column::GroupId.eq(message.chat.id))
-            .all(&self.db)
-            .await?;
+        let (replies, aliases) = self.load_effective_keywords(message.chat.id).await?;
 
-        for reply in replies {
-            if text.contains(&reply.keywords) {
-                let trigger_message_id = if text == reply.keywords {
-                    Some(message.message_id)
-                } else {
-                    None
-                };
-                self.send_reply(api, message.chat.id, &reply.reply, trigger_message_id)
-                    .await?;
+        let matches = matcher::find_matches(&replies, &aliases, text);
+        if let Some(keyword_match) = matcher::first_match(&matches) {
+            // 关键词动作先于过滤和冷却执行，这些条件只拦下回复，不能用来绕过动作
+            let deleted = self
+                .run_keyword_action(&api, message, keyword_match.reply)
+                .await?;
+
+            // 群组开启的过滤条件和忽略列表只影响关键词回复，不影响命令
+            if self.should_ignore(message).await? {
+                return Ok(());
+            }
+            // 冷却中或用户触发过于频繁时不回复，也不计入统计
+            if !self.check_cooldown(message, keyword_match.reply).await? {
                 return Ok(());
             }
+            self.record_keyword_hit(message.chat.id, &keyword_match.reply.keywords);
+            let trigger_message_id = if keyword_match.exact && !deleted {
+                Some(message.message_id)
+            } else {
+                None
+            };
+            let reply = self.render_placeholders(&keyword_match.reply.reply, message, None);
+            self.send_reply(api, message.chat.id, &reply, trigger_message_id)
+                .await?;
         }
 
         // 如果没有匹配的关键词，则忽略消息
         Ok(())
     }
 
-    async fn is_admin(&self, api: &Bot, message: &Message) -> Result<bool> {
-        if let Some(from) = &message.from {
-            let chat_id = message.chat.id;
-            let user_id = from.id;
+    /// 机器人自身的用户 ID，只在第一次使用时调用 get_me
+    async fn bot_id(&self, api: &Bot) -> Result<u64> {
+        let bot_id = self
+            .bot_id
+            .get_or_try_init(|| async { Ok::<_, anyhow::Error>(api.get_me().await?.result.id) })
+            .await?;
+        Ok(*bot_id)
+    }
 
-            let params = GetChatMemberParams::builder()
-                .chat_id(chat_id)
-                .user_id(user_id)
-                .build();
+    /// 检查用户是否仍在群组中，受限成员以 is_member 为准
+    async fn is_chat_member(&self, api: &Bot, chat_id: i64, user_id: u64) -> Result<bool> {
+        let params = GetChatMemberParams::builder()
+            .chat_id(chat_id)
+            .user_id(user_id)
+            .build();
 
-            match api.get_chat_member(&params).await {
-                Ok(response) => {
-                    let member = &response.result;
-                    Ok(matches!(
-                        member,
-                        ChatMember::Administrator(_) | ChatMember::Creator(_)
-                    ))
+        match api.get_chat_member(&params).await {
+            Ok(response) => Ok(match &response.result {
+                ChatMember::Creator(_) | ChatMember::Administrator(_) | ChatMember::Member(_) => {
+                    true
                 }
-                Err(_) => Ok(false),
-            }
-        } else {
-            Ok(false)
+                ChatMember::Restricted(member) => member.is_member,
+                ChatMember::Left(_) | ChatMember::Kicked(_) => false,
+            }),
+            Err(_) => Ok(false),
         }
     }
 
-    async fn add_keyword_reply(
+    async fn add_keyword_reply<C: ConnectionTrait>(
         &self,
+        db: &C,
         group_id: i64,
+        operation: Operation,
         keywords: String,
         reply: String,
     ) -> Result<bool> {
@@ -275,65 +518,917 @@ impl MessageHandler {
         let existing_reply = GroupReplyEntity::find()
             .filter(group_reply::Column::GroupId.eq(group_id))
             .filter(group_reply::Column::Keywords.eq(&keywords))
-            .one(&self.db)
+            .one(db)
             .await?;
 
         if let Some(existing) = existing_reply {
+            self.record_audit(
+                db,
+                AuditEntry {
+                    group_id,
+                    operation,
+                    action: AuditAction::Update,
+                    keywords: &keywords,
+                    old_keywords: None,
+                    old_reply: Some(existing.reply.clone()),
+                    new_reply: Some(reply.clone()),
+                },
+            )
+            .await?;
+
             // 如果存在，则更新回复内容
             let mut active_model: group_reply::ActiveModel = existing.into();
             active_model.reply = Set(reply);
-            active_model.update(&self.db).await?;
+            active_model.update(db).await?;
             Ok(true) // 返回 true 表示更新
         } else {
+            self.record_audit(
+                db,
+                AuditEntry {
+                    group_id,
+                    operation,
+                    action: AuditAction::Add,
+                    keywords: &keywords,
+                    old_keywords: None,
+                    old_reply: None,
+                    new_reply: Some(reply.clone()),
+                },
+            )
+            .await?;
+
             // 如果不存在，则插入新记录
             let new_reply = group_reply::ActiveModel {
                 group_id: Set(group_id),
                 keywords: Set(keywords),
                 reply: Set(reply),
+                exclude_words: Set(None),
+                require_words: Set(None),
+                cooldown_secs: Set(None),
+                action: Set(None),
+                action_duration_secs: Set(None),
             };
 
-            new_reply.insert(&self.db).await?;
+            new_reply.insert(db).await?;
             Ok(false) // 返回 false 表示添加
         }
     }
 
-    async fn delete_keyword_reply(&self, group_id: i64, keywords: String) -> Result<bool> {
-        let result = GroupReplyEntity::delete_many()
+    async fn delete_keyword_reply<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        operation: Operation,
+        keywords: String,
+    ) -> Result<bool> {
+        let existing = GroupReplyEntity::find()
             .filter(group_reply::Column::GroupId.eq(group_id))
-            .filter(group_reply::Column::Keywords.eq(keywords))
-            .exec(&self.db)
+            .filter(group_reply::Column::Keywords.eq(&keywords))
+            .one(db)
+            .await?;
+        let Some(existing) = existing else {
+            return Ok(false);
+        };
+
+        self.record_audit(
+            db,
+            AuditEntry {
+                group_id,
+                operation,
+                action: AuditAction::Delete,
+                keywords: &keywords,
+                old_keywords: None,
+                old_reply: Some(existing.reply.clone()),
+                new_reply: None,
+            },
+        )
+        .await?;
+        self.move_to_trash(db, operation, &existing).await?;
+        let result = existing.delete(db).await?;
+
+        // 同时删除该关键词的所有别名和触发统计
+        KeywordAliasEntity::delete_many()
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .filter(keyword_alias::Column::Keywords.eq(&keywords))
+            .exec(db)
+            .await?;
+        self.delete_keyword_stats(db, group_id, Some(&keywords))
             .await?;
 
         Ok(result.rows_affected > 0)
     }
 
-    async fn delete_all_keywords(&self, group_id: i64) -> Result<u64> {
+    /// 修改已有关键词的回复内容，关键词不存在时返回 false
+    async fn edit_keyword_reply(
+        &self,
+        group_id: i64,
+        operation: Operation,
+        keywords: String,
+        reply: String,
+        append: bool,
+    ) -> Result<bool> {
+        let txn = self.db.begin().await?;
+
+        let existing = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(&keywords))
+            .one(&txn)
+            .await?;
+        let Some(existing) = existing else {
+            return Ok(false);
+        };
+
+        let reply = if append {
+            format!("{}\n{}", existing.reply, reply)
+        } else {
+            reply
+        };
+        self.add_keyword_reply(&txn, group_id, operation, keywords, reply)
+            .await?;
+
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    /// 重命名关键词，保留回复内容、触发条件和别名
+    async fn rename_keyword_reply<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        operation: Operation,
+        old_keywords: String,
+        new_keywords: String,
+    ) -> Result<RenameResult> {
+        let existing = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(&old_keywords))
+            .one(db)
+            .await?;
+        let Some(existing) = existing else {
+            return Ok(RenameResult::NotFound);
+        };
+
+        // 新名称不能与其他关键词或其他关键词的别名冲突
+        let keyword_conflict = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(&new_keywords))
+            .one(db)
+            .await?;
+        if keyword_conflict.is_some() {
+            return Ok(RenameResult::Conflict);
+        }
+
+        let alias_conflict = KeywordAliasEntity::find()
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .filter(keyword_alias::Column::Alias.eq(&new_keywords))
+            .one(db)
+            .await?;
+        if let Some(alias) = alias_conflict {
+            if alias.keywords != old_keywords {
+                return Ok(RenameResult::Conflict);
+            }
+            // 新名称原本是自身的别名，改名后不再需要
+            alias.delete(db).await?;
+        }
+
+        self.record_audit(
+            db,
+            AuditEntry {
+                group_id,
+                operation,
+                action: AuditAction::Rename,
+                keywords: &new_keywords,
+                old_keywords: Some(old_keywords.clone()),
+                old_reply: None,
+                new_reply: None,
+            },
+        )
+        .await?;
+
+        // 插入新关键词并沿用原记录的所有字段
+        let renamed = group_reply::ActiveModel {
+            keywords: Set(new_keywords.clone()),
+            ..existing.clone().into()
+        };
+        renamed.reset_all().insert(db).await?;
+
+        // 别名改为指向新关键词，再删除旧关键词
+        KeywordAliasEntity::update_many()
+            .col_expr(keyword_alias::Column::Keywords, Expr::value(&new_keywords))
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .filter(keyword_alias::Column::Keywords.eq(&old_keywords))
+            .exec(db)
+            .await?;
+        existing.delete(db).await?;
+        self.rename_keyword_stat(db, group_id, &old_keywords, &new_keywords)
+            .await?;
+
+        Ok(RenameResult::Renamed)
+    }
+
+    async fn delete_all_keywords<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        operation: Operation,
+    ) -> Result<u64> {
+        // 逐条记录被清空的关键词并移入回收站，便于事后查阅或恢复
+        let existing = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .all(db)
+            .await?;
+        for reply in existing {
+            self.move_to_trash(db, operation, &reply).await?;
+            self.record_audit(
+                db,
+                AuditEntry {
+                    group_id,
+                    operation,
+                    action: AuditAction::DeleteAll,
+                    keywords: &reply.keywords,
+                    old_keywords: None,
+                    old_reply: Some(reply.reply),
+                    new_reply: None,
+                },
+            )
+            .await?;
+        }
+
         let result = GroupReplyEntity::delete_many()
             .filter(group_reply::Column::GroupId.eq(group_id))
-            .exec(&self.db)
+            .exec(db)
             .await?;
 
+        KeywordAliasEntity::delete_many()
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .exec(db)
+            .await?;
+        self.delete_keyword_stats(db, group_id, None).await?;
+
         Ok(result.rows_affected)
     }
 
-    async fn show_all_keywords(&self, api: Bot, chat_id: i64) -> Result<()> {
-        // 查询当前群组的所有关键词
-        let replies = GroupReplyEntity::find()
-            .filter(group_reply::Column::GroupId.eq(chat_id))
-            .all(&self.db)
+    async fn handle_add_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        // 通过实体 offset 获取命令后的内容
+        let content = self.get_content_after_command(message, command)?;
+
+        if content.is_empty() {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("用法: {} &lt;关键词&gt; &lt;回复内容&gt;", command),
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // 找到第一个空格或换行符的位置
+        let delimiter_pos = content.find([' ', '\n']);
+        if let Some(pos) = delimiter_pos {
+            let keywords = content[..pos].to_string();
+            let reply_content = content[pos + 1..].to_string();
+
+            // 使用 | 分隔多个触发词，第一个作为主关键词，其余作为别名
+            let triggers = self.split_triggers(&keywords);
+
+            if triggers.is_empty() || reply_content.is_empty() {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!("用法: {} &lt;关键词&gt; &lt;回复内容&gt;", command),
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+
+            // 处理回复内容，检查消息实体中的 code 类型并用 <code> 标签包裹
+            let processed_reply = self
+                .process_reply_with_entities(&reply_content, message)
+                .await?;
+
+            // 如果第一个触发词是已有关键词的别名，则更新其所属的关键词
+            let keywords = self.resolve_keyword(group_id, &triggers[0]).await?;
+
+            // 保存到数据库
+            let is_updated = self
+                .add_keyword_reply(
+                    &self.db,
+                    group_id,
+                    self.begin_operation(message),
+                    keywords.clone(),
+                    processed_reply,
+                )
+                .await?;
+            let mut message_text = if is_updated {
+                format!(
+                    "关键词 <code>{}</code> 的回复内容已更新成功！",
+                    self.escape_html(&keywords)
+                )
+            } else {
+                format!(
+                    "关键词 <code>{}</code> 回复已添加成功！",
+                    self.escape_html(&keywords)
+                )
+            };
+
+            if triggers.len() > 1 {
+                let skipped = self
+                    .add_keyword_aliases(&self.db, group_id, &keywords, &triggers[1..])
+                    .await?;
+                message_text.push_str(
+                    &self.format_alias_result(triggers.len() - 1 - skipped.len(), &skipped),
+                );
+            }
+
+            self.send_reply(api, message.chat.id, &message_text, None)
+                .await?;
+        } else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("用法: {} &lt;关键词&gt; &lt;回复内容&gt;", command),
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        Ok(())
+    }
+
+    async fn handle_del_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        if content.is_empty() {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("用法: {} &lt;关键词&gt;", command),
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // 别名会解析为其所属的关键词，整条回复连同别名一起删除
+        let keywords = self.resolve_keyword(group_id, content.trim()).await?;
+
+        // 删除关键词
+        match self
+            .delete_keyword_reply(
+                &self.db,
+                group_id,
+                self.begin_operation(message),
+                keywords.clone(),
+            )
+            .await
+        {
+            Ok(true) => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!(
+                        "关键词 <code>{}</code> 已删除成功！",
+                        self.escape_html(&keywords)
+                    ),
+                    None,
+                )
+                .await?;
+            }
+            Ok(false) => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords)),
+                    None,
+                )
+                .await?;
+            }
+            Err(e) => {
+                eprintln!("删除关键词时出错: {}", e);
+                self.send_reply(api, message.chat.id, "删除关键词时出错，请稍后重试", None)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_alias_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let mut parts = content.split_whitespace();
+        let (Some(keywords), Some(aliases)) = (parts.next(), parts.next()) else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "用法: /alias &lt;关键词&gt; &lt;别名1|别名2...&gt;",
+                None,
+            )
             .await?;
+            return Ok(());
+        };
 
-        if replies.is_empty() {
-            self.send_reply(api, chat_id, "当前群组还没有设置任何关键词回复。", None)
+        let keywords = self.resolve_keyword(group_id, keywords).await?;
+        let exists = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(&keywords))
+            .one(&self.db)
+            .await?
+            .is_some();
+        if !exists {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords)),
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let aliases = self.split_triggers(aliases);
+        let skipped = self
+            .add_keyword_aliases(&self.db, group_id, &keywords, &aliases)
+            .await?;
+        let message_text = format!(
+            "关键词 <code>{}</code>{}",
+            self.escape_html(&keywords),
+            self.format_alias_result(aliases.len() - skipped.len(), &skipped)
+        );
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_unalias_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let alias = content.trim();
+        if alias.is_empty() {
+            self.send_reply(api, message.chat.id, "用法: /unalias &lt;别名&gt;", None)
                 .await?;
+            return Ok(());
+        }
+
+        let result = KeywordAliasEntity::delete_many()
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .filter(keyword_alias::Column::Alias.eq(alias))
+            .exec(&self.db)
+            .await?;
+        let message_text = if result.rows_affected > 0 {
+            format!("别名 <code>{}</code> 已删除成功！", self.escape_html(alias))
         } else {
-            let mut message = "<b>当前群组的关键词列表:</b>\n\n".to_string();
-            for reply in replies.iter() {
-                message.push_str(&format!("<code>{}</code>\n", reply.keywords));
+            format!("未找到别名 <code>{}</code>", self.escape_html(alias))
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_rename_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let mut parts = content.split_whitespace();
+        let (Some(old_keywords), Some(new_keywords), None) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "用法: /rename &lt;原关键词&gt; &lt;新关键词&gt;",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let old_keywords = self.resolve_keyword(group_id, old_keywords).await?;
+        let txn = self.db.begin().await?;
+        let result = self
+            .rename_keyword_reply(
+                &txn,
+                group_id,
+                self.begin_operation(message),
+                old_keywords.clone(),
+                new_keywords.to_string(),
+            )
+            .await?;
+        txn.commit().await?;
+
+        let message_text = match result {
+            RenameResult::Renamed => format!(
+                "关键词 <code>{}</code> 已重命名为 <code>{}</code>",
+                self.escape_html(&old_keywords),
+                self.escape_html(new_keywords)
+            ),
+            RenameResult::NotFound => {
+                format!(
+                    "未找到关键词 <code>{}</code>",
+                    self.escape_html(&old_keywords)
+                )
             }
-            self.send_reply(api, chat_id, &message, None).await?;
+            RenameResult::Conflict => format!(
+                "关键词或别名 <code>{}</code> 已存在，请先删除或换一个名称",
+                self.escape_html(new_keywords)
+            ),
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_del_all_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let count = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .count(&self.db)
+            .await?;
+        if count == 0 {
+            self.send_reply(api, message.chat.id, "当前群组没有设置任何关键词", None)
+                .await?;
+            return Ok(());
+        }
+
+        // 删除所有关键词前需要发起者确认
+        let text = format!(
+            "确定要删除当前群组的 {} 个关键词吗？删除的关键词会移入回收站。",
+            count
+        );
+        self.request_confirmation(api, message, group_id, PendingAction::DeleteAll, &text)
+            .await
+    }
+
+    async fn handle_edit_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let append = command == "/append";
+        let usage = if append {
+            "用法: /append &lt;关键词&gt; &lt;追加内容&gt;"
+        } else {
+            "用法: /edit &lt;关键词&gt; &lt;新回复内容&gt;"
+        };
+
+        let content = self.get_content_after_command(message, command)?;
+        let Some(pos) = content.find([' ', '\n']) else {
+            self.send_reply(api, message.chat.id, usage, None).await?;
+            return Ok(());
+        };
+        let keywords = content[..pos].to_string();
+        let reply_content = content[pos + 1..].to_string();
+        if keywords.is_empty() || reply_content.trim().is_empty() {
+            self.send_reply(api, message.chat.id, usage, None).await?;
+            return Ok(());
         }
 
+        let processed_reply = self
+            .process_reply_with_entities(&reply_content, message)
+            .await?;
+        let keywords = self.resolve_keyword(group_id, &keywords).await?;
+
+        let message_text = if self
+            .edit_keyword_reply(
+                group_id,
+                self.begin_operation(message),
+                keywords.clone(),
+                processed_reply,
+                append,
+            )
+            .await?
+        {
+            if append {
+                format!(
+                    "已向关键词 <code>{}</code> 的回复追加内容！",
+                    self.escape_html(&keywords)
+                )
+            } else {
+                format!(
+                    "关键词 <code>{}</code> 的回复内容已更新成功！",
+                    self.escape_html(&keywords)
+                )
+            }
+        } else {
+            format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords))
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle_condition_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+        kind: ConditionKind,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let mut parts = content.split_whitespace();
+        let Some(keywords) = parts.next() else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("用法: {} &lt;关键词&gt; [词1|词2...]", command),
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let keywords = self.resolve_keyword(group_id, keywords).await?;
+        let words = self.split_triggers(&parts.collect::<Vec<_>>().join(" "));
+
+        let existing = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(&keywords))
+            .one(&self.db)
+            .await?;
+        let Some(existing) = existing else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords)),
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let value = if words.is_empty() {
+            None
+        } else {
+            Some(words.join("|"))
+        };
+        let mut active_model: group_reply::ActiveModel = existing.into();
+        match kind {
+            ConditionKind::Exclude => active_model.exclude_words = Set(value),
+            ConditionKind::Require => active_model.require_words = Set(value),
+        }
+        active_model.update(&self.db).await?;
+
+        let label = match kind {
+            ConditionKind::Exclude => "排除词",
+            ConditionKind::Require => "附加条件",
+        };
+        let message_text = if words.is_empty() {
+            format!(
+                "关键词 <code>{}</code> 的{}已清除！",
+                self.escape_html(&keywords),
+                label
+            )
+        } else {
+            let words: Vec<String> = words
+                .iter()
+                .map(|word| format!("<code>{}</code>", self.escape_html(word)))
+                .collect();
+            format!(
+                "关键词 <code>{}</code> 的{}已设置为: {}",
+                self.escape_html(&keywords),
+                label,
+                words.join(", ")
+            )
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn find_keyword_reply(
+        &self,
+        group_id: i64,
+        keywords: &str,
+    ) -> Result<Option<group_reply::Model>> {
+        let reply = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(keywords))
+            .one(&self.db)
+            .await?;
+
+        Ok(reply)
+    }
+
+    /// 查询群组匹配时实际生效的关键词及别名
+    ///
+    /// 优先级从高到低为：本群关键词、订阅的来源群组关键词、全局关键词。
+    /// 同名关键词只保留优先级最高的一条，群组可以通过 `/global off` 关闭全局关键词
+    async fn load_effective_keywords(
+        &self,
+        group_id: i64,
+    ) -> Result<(Vec<group_reply::Model>, HashMap<String, Vec<String>>)> {
+        let mut replies = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?;
+        let mut aliases = self.load_aliases(group_id).await?;
+
+        let subscription = GroupSubscriptionEntity::find_by_id(group_id)
+            .one(&self.db)
+            .await?;
+        if let Some(subscription) = subscription {
+            self.merge_inherited_keywords(&mut replies, &mut aliases, subscription.source_group_id)
+                .await?;
+        }
+
+        let global_disabled = GroupSettingEntity::find_by_id(group_id)
+            .one(&self.db)
+            .await?
+            .is_some_and(|setting| setting.global_disabled);
+        if group_id != GLOBAL_GROUP_ID && !global_disabled {
+            self.merge_inherited_keywords(&mut replies, &mut aliases, GLOBAL_GROUP_ID)
+                .await?;
+        }
+
+        Ok((replies, aliases))
+    }
+
+    /// 将其他来源的关键词追加到列表末尾，已存在同名关键词或别名的条目被跳过
+    async fn merge_inherited_keywords(
+        &self,
+        replies: &mut Vec<group_reply::Model>,
+        aliases: &mut HashMap<String, Vec<String>>,
+        source_group_id: i64,
+    ) -> Result<()> {
+        let source_replies = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(source_group_id))
+            .all(&self.db)
+            .await?;
+        let mut source_aliases = self.load_aliases(source_group_id).await?;
+
+        for reply in source_replies {
+            let overridden = replies.iter().any(|own| own.keywords == reply.keywords)
+                || aliases
+                    .values()
+                    .flatten()
+                    .any(|alias| *alias == reply.keywords);
+            if overridden {
+                continue;
+            }
+            if let Some(source) = source_aliases.remove(&reply.keywords) {
+                aliases.insert(reply.keywords.clone(), source);
+            }
+            replies.push(reply);
+        }
+
+        Ok(())
+    }
+
+    /// 将别名解析为其所属的主关键词，非别名原样返回
+    async fn resolve_keyword(&self, group_id: i64, keywords: &str) -> Result<String> {
+        let alias = KeywordAliasEntity::find()
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .filter(keyword_alias::Column::Alias.eq(keywords))
+            .one(&self.db)
+            .await?;
+
+        Ok(alias.map_or_else(|| keywords.to_string(), |alias| alias.keywords))
+    }
+
+    /// 查询群组的所有别名，按主关键词分组
+    async fn load_aliases(&self, group_id: i64) -> Result<HashMap<String, Vec<String>>> {
+        let aliases = KeywordAliasEntity::find()
+            .filter(keyword_alias::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?;
+
+        let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+        for alias in aliases {
+            grouped.entry(alias.keywords).or_default().push(alias.alias);
+        }
+
+        Ok(grouped)
+    }
+
+    /// 为关键词添加别名，返回因与已有主关键词冲突而跳过的别名
+    async fn add_keyword_aliases<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        keywords: &str,
+        aliases: &[String],
+    ) -> Result<Vec<String>> {
+        let mut skipped = Vec::new();
+
+        for alias in aliases {
+            if alias == keywords {
+                continue;
+            }
+
+            let conflict = GroupReplyEntity::find()
+                .filter(group_reply::Column::GroupId.eq(group_id))
+                .filter(group_reply::Column::Keywords.eq(alias))
+                .one(db)
+                .await?;
+            if conflict.is_some() {
+                skipped.push(alias.clone());
+                continue;
+            }
+
+            // 别名已存在时改为指向当前关键词
+            let existing = KeywordAliasEntity::find()
+                .filter(keyword_alias::Column::GroupId.eq(group_id))
+                .filter(keyword_alias::Column::Alias.eq(alias))
+                .one(db)
+                .await?;
+
+            if let Some(existing) = existing {
+                let mut active_model: keyword_alias::ActiveModel = existing.into();
+                active_model.keywords = Set(keywords.to_string());
+                active_model.update(db).await?;
+            } else {
+                let new_alias = keyword_alias::ActiveModel {
+                    group_id: Set(group_id),
+                    alias: Set(alias.clone()),
+                    keywords: Set(keywords.to_string()),
+                };
+                new_alias.insert(db).await?;
+            }
+        }
+
+        Ok(skipped)
+    }
+
+    fn split_triggers(&self, keywords: &str) -> Vec<String> {
+        let mut triggers: Vec<String> = Vec::new();
+        for trigger in keywords.split('|').map(str::trim) {
+            if !trigger.is_empty() && !triggers.iter().any(|t| t == trigger) {
+                triggers.push(trigger.to_string());
+            }
+        }
+        triggers
+    }
+
+    fn format_alias_result(&self, added: usize, skipped: &[String]) -> String {
+        let mut text = String::new();
+        if added > 0 {
+            text.push_str(&format!("\n已添加 {} 个别名", added));
+        }
+        if !skipped.is_empty() {
+            let skipped: Vec<String> = skipped
+                .iter()
+                .map(|alias| format!("<code>{}</code>", self.escape_html(alias)))
+                .collect();
+            text.push_str(&format!(
+                "\n以下别名与已有关键词冲突，已跳过: {}",
+                skipped.join(", ")
+            ));
+        }
+        text
+    }
+
+    async fn answer_callback(
+        &self,
+        api: &Bot,
+        query: &CallbackQuery,
+        text: Option<&str>,
+    ) -> Result<()> {
+        let params = AnswerCallbackQueryParams::builder()
+            .callback_query_id(query.id.clone())
+            .maybe_text(text.map(str::to_string))
+            .build();
+        if let Err(e) = api.answer_callback_query(&params).await {
+            eprintln!("应答回调查询时出错: {}", e);
+        }
         Ok(())
     }
 
@@ -460,6 +1555,12 @@ impl MessageHandler {
         }
     }
 
+    fn escape_html(&self, text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
     fn utf8_to_utf16_offset(&self, text: &str, utf8_offset: usize) -> Result<usize> {
         let mut utf16_count = 0;
         let mut utf8_count = 0;
@@ -498,35 +1599,29 @@ impl MessageHandler {
         chat_id: i64,
         text: &str,
         trigger_message_id: Option<i32>,
+    ) -> Result<()> {
+        self.send_reply_with_markup(api, chat_id, text, trigger_message_id, None)
+            .await
+    }
+
+    async fn send_reply_with_markup(
+        &self,
+        api: Bot,
+        chat_id: i64,
+        text: &str,
+        trigger_message_id: Option<i32>,
+        reply_markup: Option<InlineKeyboardMarkup>,
     ) -> Result<()> {
         let reply_params = SendMessageParams::builder()
             .chat_id(chat_id)
             .text(text)
             .parse_mode(ParseMode::Html)
+            .maybe_reply_markup(reply_markup.map(ReplyMarkup::InlineKeyboardMarkup))
             .build();
 
         match api.send_message(&reply_params).await {
             Ok(response) => {
-                let message_id = response.result.message_id;
-                tokio::spawn(async move {
-                    tokio::time::sleep(Duration::from_secs(AUTO_DELETE_AFTER_SECS)).await;
-                    let delete_params = DeleteMessageParams::builder()
-                        .chat_id(chat_id)
-                        .message_id(message_id)
-                        .build();
-                    if let Err(e) = api.delete_message(&delete_params).await {
-                        eprintln!("自动删除消息时出错: {}", e);
-                    }
-                    if let Some(trigger_id) = trigger_message_id {
-                        let delete_params = DeleteMessageParams::builder()
-                            .chat_id(chat_id)
-                            .message_id(trigger_id)
-                            .build();
-                        if let Err(e) = api.delete_message(&delete_params).await {
-                            eprintln!("自动删除触发消息时出错: {}", e);
-                        }
-                    }
-                });
+                self.delete_later(api, chat_id, response.result.message_id, trigger_message_id);
             }
             Err(e) => {
                 eprintln!("发送回复时出错: {}", e);
@@ -535,4 +1630,70 @@ impl MessageHandler {
 
         Ok(())
     }
+
+    /// 在 AUTO_DELETE_AFTER_SECS 秒后删除机器人发送的消息，以及触发它的消息
+    fn delete_later(
+        &self,
+        api: Bot,
+        chat_id: i64,
+        message_id: i32,
+        trigger_message_id: Option<i32>,
+    ) {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(AUTO_DELETE_AFTER_SECS)).await;
+            let delete_params = DeleteMessageParams::builder()
+                .chat_id(chat_id)
+                .message_id(message_id)
+                .build();
+            if let Err(e) = api.delete_message(&delete_params).await {
+                eprintln!("自动删除消息时出错: {}", e);
+            }
+            if let Some(trigger_id) = trigger_message_id {
+                let delete_params = DeleteMessageParams::builder()
+                    .chat_id(chat_id)
+                    .message_id(trigger_id)
+                    .build();
+                if let Err(e) = api.delete_message(&delete_params).await {
+                    eprintln!("自动删除触发消息时出错: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// 不连接数据库的处理器，用于测试不访问数据库的辅助方法
+#[cfg(test)]
+fn test_handler() -> MessageHandler {
+    let config = Config {
+        bot_token: String::new(),
+        database_url: String::new(),
+        owner_id: None,
+        inline_group_ids: Vec::new(),
+        admin_required_right: None,
+        trash_retention_days: 30,
+        cooldown_state_file: None,
+        warn_expire_days: 30,
+    };
+    MessageHandler::new(DatabaseConnection::Disconnected, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_handler;
+
+    #[test]
+    fn split_triggers_trims_and_deduplicates() {
+        let handler = test_handler();
+        assert_eq!(
+            handler.split_triggers(" 群规 | 规矩||群规|rules "),
+            vec!["群规", "规矩", "rules"]
+        );
+        assert!(handler.split_triggers("| |").is_empty());
+    }
+
+    #[test]
+    fn escape_html_escapes_markup() {
+        let handler = test_handler();
+        assert_eq!(handler.escape_html("a<b>&c"), "a&lt;b&gt;&amp;c");
+    }
 }
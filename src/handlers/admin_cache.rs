@@ -0,0 +1,132 @@
+use super::MessageHandler;
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::GetChatAdministratorsParams;
+use frankenstein::types::{ChatMember, ChatMemberUpdated, Message};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+// 管理员列表的缓存时间，群组成员变更或 /reload 时会提前失效
+const ADMIN_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// 各群组管理员列表的缓存，在所有消息处理任务之间共享
+#[derive(Clone, Default)]
+pub(super) struct AdminCache {
+    entries: Arc<Mutex<HashMap<i64, CachedAdmins>>>,
+}
+
+struct CachedAdmins {
+    fetched_at: Instant,
+    admins: Arc<Vec<ChatMember>>,
+}
+
+impl AdminCache {
+    fn get(&self, chat_id: i64) -> Option<Arc<Vec<ChatMember>>> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(&chat_id)
+            .filter(|cached| cached.fetched_at.elapsed() < ADMIN_CACHE_TTL)
+            .map(|cached| cached.admins.clone())
+    }
+
+    fn insert(&self, chat_id: i64, admins: Arc<Vec<ChatMember>>) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.insert(
+            chat_id,
+            CachedAdmins {
+                fetched_at: Instant::now(),
+                admins,
+            },
+        );
+    }
+
+    fn remove(&self, chat_id: i64) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.remove(&chat_id);
+    }
+}
+
+impl MessageHandler {
+    /// 获取群组的管理员列表，优先使用缓存，机器人不在群组中等情况下返回 None
+    pub(super) async fn chat_admins(
+        &self,
+        api: &Bot,
+        chat_id: i64,
+    ) -> Result<Option<Arc<Vec<ChatMember>>>> {
+        if let Some(admins) = self.admin_cache.get(chat_id) {
+            return Ok(Some(admins));
+        }
+
+        let params = GetChatAdministratorsParams::builder()
+            .chat_id(chat_id)
+            .build();
+        match api.get_chat_administrators(&params).await {
+            Ok(response) => {
+                let admins = Arc::new(response.result);
+                self.admin_cache.insert(chat_id, admins.clone());
+                Ok(Some(admins))
+            }
+            Err(e) => {
+                eprintln!("获取群组 {} 的管理员列表时出错: {}", chat_id, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// 在管理员列表中查找指定用户
+    pub(super) async fn find_chat_admin(
+        &self,
+        api: &Bot,
+        chat_id: i64,
+        user_id: u64,
+    ) -> Result<Option<ChatMember>> {
+        let Some(admins) = self.chat_admins(api, chat_id).await? else {
+            return Ok(None);
+        };
+
+        let admin = admins.iter().find(|member| match member {
+            ChatMember::Creator(owner) => owner.user.id == user_id,
+            ChatMember::Administrator(admin) => admin.user.id == user_id,
+            _ => false,
+        });
+
+        Ok(admin.cloned())
+    }
+
+    pub(super) fn invalidate_admin_cache(&self, chat_id: i64) {
+        self.admin_cache.remove(chat_id);
+    }
+
+    /// 群组成员的管理员身份变化时丢弃该群组的缓存
+    pub async fn handle_chat_member_update(&self, update: ChatMemberUpdated) -> Result<()> {
+        let is_admin = |member: &ChatMember| {
+            matches!(
+                member,
+                ChatMember::Creator(_) | ChatMember::Administrator(_)
+            )
+        };
+        if is_admin(&update.old_chat_member) || is_admin(&update.new_chat_member) {
+            self.invalidate_admin_cache(update.chat.id);
+        }
+
+        Ok(())
+    }
+
+    /// 处理 /reload，权限检查之前缓存已被清空，这里只汇报刷新结果
+    pub(super) async fn handle_reload_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let text = match self.chat_admins(&api, group_id).await? {
+            Some(admins) => format!("已刷新管理员列表，当前共有 {} 名管理员", admins.len()),
+            None => "无法获取管理员列表，请确认机器人仍在该群组中".to_string(),
+        };
+        self.send_reply(api, message.chat.id, &text, None).await?;
+
+        Ok(())
+    }
+}
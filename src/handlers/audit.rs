@@ -0,0 +1,254 @@
+use super::MessageHandler;
+use super::keyword_list::{ListKind, ListSort};
+use crate::entities::keyword_audit::{self, Entity as KeywordAuditEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use std::str::FromStr;
+
+// 关键词需要写入翻页按钮的回调数据，与搜索词使用同样的长度限制
+const MAX_HISTORY_KEYWORD_BYTES: usize = 32;
+
+// 变更记录中回复内容的预览长度
+const REPLY_PREVIEW_CHARS: usize = 40;
+
+/// 关键词变更的种类
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum AuditAction {
+    Add,
+    Update,
+    Delete,
+    DeleteAll,
+    Rename,
+    Restore,
+}
+
+impl AuditAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Add => "add",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::DeleteAll => "delete_all",
+            AuditAction::Rename => "rename",
+            AuditAction::Restore => "restore",
+        }
+    }
+
+    pub(super) fn label(action: &str) -> &str {
+        match action {
+            "add" => "添加",
+            "update" => "修改",
+            "delete" => "删除",
+            "delete_all" => "清空",
+            "rename" => "重命名",
+            "restore" => "恢复",
+            other => other,
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(AuditAction::Add),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            "delete_all" => Ok(AuditAction::DeleteAll),
+            "rename" => Ok(AuditAction::Rename),
+            "restore" => Ok(AuditAction::Restore),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 一次用户操作，同一操作写入的变更记录可以被 /undo 一起撤销
+#[derive(Clone, Copy)]
+pub(super) struct Operation {
+    pub id: i64,
+    pub actor_id: i64,
+}
+
+impl Operation {
+    /// 开始新的操作，操作 ID 取当前的微秒时间戳
+    pub(super) fn new(actor_id: i64) -> Self {
+        Operation {
+            id: chrono::Utc::now().timestamp_micros(),
+            actor_id,
+        }
+    }
+}
+
+/// 一条待写入的变更记录
+pub(super) struct AuditEntry<'a> {
+    pub group_id: i64,
+    pub operation: Operation,
+    pub action: AuditAction,
+    pub keywords: &'a str,
+    pub old_keywords: Option<String>,
+    pub old_reply: Option<String>,
+    pub new_reply: Option<String>,
+}
+
+impl MessageHandler {
+    /// 消息的操作者 ID，匿名管理员以群组身份发言时记为群组 ID
+    pub(super) fn actor_id(&self, message: &Message) -> i64 {
+        match (&message.sender_chat, &message.from) {
+            (Some(chat), _) => chat.id,
+            (None, Some(from)) => from.id as i64,
+            (None, None) => 0,
+        }
+    }
+
+    /// 为一条修改命令开始新的操作
+    pub(super) fn begin_operation(&self, message: &Message) -> Operation {
+        Operation::new(self.actor_id(message))
+    }
+
+    /// 写入一条变更记录，与关键词的修改处于同一连接或事务中
+    pub(super) async fn record_audit<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        entry: AuditEntry<'_>,
+    ) -> Result<()> {
+        let audit = keyword_audit::ActiveModel {
+            group_id: Set(entry.group_id),
+            actor_id: Set(entry.operation.actor_id),
+            action: Set(entry.action.as_str().to_string()),
+            keywords: Set(entry.keywords.to_string()),
+            old_reply: Set(entry.old_reply),
+            new_reply: Set(entry.new_reply),
+            created_at: Set(chrono::Utc::now().timestamp()),
+            operation_id: Set(entry.operation.id),
+            undoable: Set(true),
+            old_keywords: Set(entry.old_keywords),
+            ..Default::default()
+        };
+        audit.insert(db).await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_history_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let keywords = content.trim();
+        if keywords.len() > MAX_HISTORY_KEYWORD_BYTES {
+            self.send_reply(api, message.chat.id, "关键词过长，请缩短后重试", None)
+                .await?;
+            return Ok(());
+        }
+
+        match self
+            .render_list_page(group_id, ListKind::History, ListSort::Default, 0, keywords)
+            .await?
+        {
+            Some(page) => {
+                self.send_reply_with_markup(api, message.chat.id, &page.text, None, page.keyboard)
+                    .await?;
+            }
+            None => {
+                let text = if keywords.is_empty() {
+                    "当前群组还没有任何变更记录".to_string()
+                } else {
+                    format!(
+                        "关键词 <code>{}</code> 没有变更记录",
+                        self.escape_html(keywords)
+                    )
+                };
+                self.send_reply(api, message.chat.id, &text, None).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 按时间倒序生成变更记录的列表行，指定关键词时只显示该关键词的记录
+    pub(super) async fn history_list_lines(
+        &self,
+        group_id: i64,
+        keywords: &str,
+    ) -> Result<Vec<String>> {
+        let mut query = KeywordAuditEntity::find()
+            .filter(keyword_audit::Column::GroupId.eq(group_id))
+            .order_by_desc(keyword_audit::Column::Id);
+        if !keywords.is_empty() {
+            // 重命名记录在新旧关键词下都能查到
+            query = query.filter(
+                Condition::any()
+                    .add(keyword_audit::Column::Keywords.eq(keywords))
+                    .add(keyword_audit::Column::OldKeywords.eq(keywords)),
+            );
+        }
+
+        let lines = query
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|audit| {
+                let keywords = match &audit.old_keywords {
+                    Some(old) => format!(
+                        "<code>{}</code> → <code>{}</code>",
+                        self.escape_html(old),
+                        self.escape_html(&audit.keywords)
+                    ),
+                    None => format!("<code>{}</code>", self.escape_html(&audit.keywords)),
+                };
+                let mut line = format!(
+                    "{} <code>{}</code> {} {}",
+                    self.format_timestamp(audit.created_at),
+                    audit.actor_id,
+                    AuditAction::label(&audit.action),
+                    keywords
+                );
+                match (&audit.old_reply, &audit.new_reply) {
+                    (Some(old), Some(new)) => line.push_str(&format!(
+                        "\n    {} → {}",
+                        self.reply_preview(old),
+                        self.reply_preview(new)
+                    )),
+                    (Some(old), None) => {
+                        line.push_str(&format!("\n    原回复: {}", self.reply_preview(old)))
+                    }
+                    (None, Some(new)) => {
+                        line.push_str(&format!("\n    回复: {}", self.reply_preview(new)))
+                    }
+                    (None, None) => {}
+                }
+                line
+            })
+            .collect();
+
+        Ok(lines)
+    }
+
+    pub(super) fn format_timestamp(&self, timestamp: i64) -> String {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 回复内容可能包含 HTML 标签，预览时按原文转义后截断
+    pub(super) fn reply_preview(&self, reply: &str) -> String {
+        let mut preview: String = reply.chars().take(REPLY_PREVIEW_CHARS).collect();
+        if preview.len() < reply.len() {
+            preview.push('…');
+        }
+        format!("<code>{}</code>", self.escape_html(&preview))
+    }
+}
@@ -0,0 +1,524 @@
+use super::MessageHandler;
+use super::moderation::KeywordAction;
+use crate::config::AdminRight;
+use crate::entities::captcha_challenge::{self, Entity as CaptchaChallengeEntity};
+use crate::entities::group_setting;
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::ParseMode;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::{
+    BanChatMemberParams, DeleteMessageParams, GetChatParams, RestrictChatMemberParams,
+    SendMessageParams, UnbanChatMemberParams,
+};
+use frankenstein::types::{
+    CallbackQuery, ChatPermissions, InlineKeyboardButton, InlineKeyboardMarkup,
+    MaybeInaccessibleMessage, Message, ReplyMarkup, User,
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use std::hash::{BuildHasher, RandomState};
+use std::str::FromStr;
+
+pub(super) const CALLBACK_PREFIX: &str = "captcha";
+
+// 按钮验证的答案，只要本人点击即可通过
+const BUTTON_ANSWER: &str = "ok";
+
+// 验证时限的范围
+const MIN_CAPTCHA_TIMEOUT_SECS: u32 = 30;
+const MAX_CAPTCHA_TIMEOUT_SECS: u32 = 60 * 60;
+
+// 验证期间的禁言比验证时限多保留的时间，留给后台任务移出超时成员，
+// 移出一直失败时禁言也会自动解除，不会让成员永久无法发言
+const CAPTCHA_MUTE_MARGIN_SECS: u32 = 10 * 60;
+
+// 算术题提供的选项数量
+const MATH_OPTION_COUNT: usize = 4;
+
+/// 新成员入群验证的方式
+#[derive(Clone, Copy)]
+enum CaptchaMode {
+    /// 点击“我不是机器人”按钮
+    Button,
+    /// 从选项中选出加法题的答案
+    Math,
+}
+
+impl CaptchaMode {
+    const ALL: [CaptchaMode; 2] = [CaptchaMode::Button, CaptchaMode::Math];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CaptchaMode::Button => "button",
+            CaptchaMode::Math => "math",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            CaptchaMode::Button => "点击按钮",
+            CaptchaMode::Math => "算术题",
+        }
+    }
+}
+
+impl FromStr for CaptchaMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CaptchaMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// 生成小于 bound 的随机数，验证题只需要不可预测，不需要密码学强度
+fn random_below(bound: u64) -> u64 {
+    RandomState::new().hash_one(chrono::Utc::now().timestamp_nanos_opt()) % bound
+}
+
+impl MessageHandler {
+    /// 禁言新成员并发送验证消息，未能发起验证时返回 false
+    pub(super) async fn start_captcha(
+        &self,
+        api: &Bot,
+        message: &Message,
+        setting: &group_setting::Model,
+        member: &User,
+    ) -> Result<bool> {
+        let chat_id = message.chat.id;
+        if !self
+            .bot_has_right(api, chat_id, AdminRight::RestrictMembers)
+            .await?
+        {
+            // 大量成员同时加入时只提示一次，开启验证时已经提醒过缺少权限
+            if self.right_notices.should_notify(chat_id, "captcha") {
+                self.send_reply(
+                    api.clone(),
+                    chat_id,
+                    "机器人缺少 <code>can_restrict_members</code> 管理员权限，无法进行入群验证",
+                    None,
+                )
+                .await?;
+            }
+            return Ok(false);
+        }
+
+        let timeout_secs = setting.captcha_timeout_secs.max(0) as u32;
+        let mute_secs = (timeout_secs + CAPTCHA_MUTE_MARGIN_SECS) as i32;
+        if !self
+            .restrict_member(
+                api,
+                chat_id,
+                member.id,
+                KeywordAction::Mute,
+                Some(mute_secs),
+            )
+            .await?
+        {
+            return Ok(false);
+        }
+
+        let mention = self.mention_user(member);
+        let timeout = self.format_duration(timeout_secs);
+        let button = |text: String, answer: &str| {
+            InlineKeyboardButton::builder()
+                .text(text)
+                .callback_data(format!("{}:{}:{}", CALLBACK_PREFIX, member.id, answer))
+                .build()
+        };
+        let mode = setting
+            .captcha_mode
+            .parse::<CaptchaMode>()
+            .unwrap_or(CaptchaMode::Button);
+        let (text, answer, buttons) = match mode {
+            CaptchaMode::Button => (
+                format!(
+                    "{} 欢迎加入，请在 {} 内点击下方按钮完成验证，超时将被移出群组",
+                    mention, timeout
+                ),
+                BUTTON_ANSWER.to_string(),
+                vec![button("我不是机器人".to_string(), BUTTON_ANSWER)],
+            ),
+            CaptchaMode::Math => {
+                let a = random_below(9) + 1;
+                let b = random_below(9) + 1;
+                let answer = a + b;
+                // 从 2 到 18 中挑选不重复的干扰项，再把答案放到随机位置
+                let mut options = Vec::with_capacity(MATH_OPTION_COUNT);
+                while options.len() < MATH_OPTION_COUNT - 1 {
+                    let option = random_below(17) + 2;
+                    if option != answer && !options.contains(&option) {
+                        options.push(option);
+                    }
+                }
+                options.insert(random_below(MATH_OPTION_COUNT as u64) as usize, answer);
+                (
+                    format!(
+                        "{} 欢迎加入，请在 {} 内选出 <b>{} + {}</b> 的答案完成验证，答错或超时将被移出群组",
+                        mention, timeout, a, b
+                    ),
+                    answer.to_string(),
+                    options
+                        .iter()
+                        .map(|option| button(option.to_string(), &option.to_string()))
+                        .collect(),
+                )
+            }
+        };
+
+        // 重新加入的用户改用新的验证，旧的验证消息一并删除
+        self.cancel_captcha(api, chat_id, member.id).await?;
+
+        // 验证消息需要保留到验证结束，不走自动删除
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(vec![buttons])
+            .build();
+        let params = SendMessageParams::builder()
+            .chat_id(chat_id)
+            .text(text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(ReplyMarkup::InlineKeyboardMarkup(keyboard))
+            .build();
+        let message_id = match api.send_message(&params).await {
+            Ok(response) => response.result.message_id,
+            Err(e) => {
+                // 成员看不到验证消息就无法完成验证，解除禁言而不是等到超时移出
+                eprintln!("发送入群验证消息时出错: {}", e);
+                self.lift_restriction(api, chat_id, member.id).await?;
+                return Ok(false);
+            }
+        };
+
+        let challenge = captcha_challenge::ActiveModel {
+            group_id: Set(chat_id),
+            user_id: Set(member.id as i64),
+            message_id: Set(Some(message_id)),
+            answer: Set(answer),
+            expires_at: Set(chrono::Utc::now().timestamp() + i64::from(timeout_secs)),
+        };
+        challenge.insert(&self.db).await?;
+
+        Ok(true)
+    }
+
+    /// 丢弃成员未完成的验证并删除验证消息，返回是否存在未完成的验证
+    pub(super) async fn cancel_captcha(
+        &self,
+        api: &Bot,
+        chat_id: i64,
+        user_id: u64,
+    ) -> Result<bool> {
+        let Some(challenge) = CaptchaChallengeEntity::find_by_id((chat_id, user_id as i64))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        self.delete_captcha_message(api, &challenge).await;
+        CaptchaChallengeEntity::delete_by_id((chat_id, user_id as i64))
+            .exec(&self.db)
+            .await?;
+
+        Ok(true)
+    }
+
+    pub(super) async fn handle_captcha_callback(
+        &self,
+        api: &Bot,
+        query: &CallbackQuery,
+        args: &str,
+    ) -> Result<()> {
+        let Some(MaybeInaccessibleMessage::Message(message)) = &query.message else {
+            self.answer_callback(api, query, Some("消息已过期")).await?;
+            return Ok(());
+        };
+        let Some((user_id, answer)) = args
+            .split_once(':')
+            .and_then(|(user_id, answer)| Some((user_id.parse::<u64>().ok()?, answer)))
+        else {
+            self.answer_callback(api, query, None).await?;
+            return Ok(());
+        };
+        if query.from.id != user_id {
+            self.answer_callback(api, query, Some("这不是你的验证"))
+                .await?;
+            return Ok(());
+        }
+
+        let chat_id = message.chat.id;
+        let Some(challenge) = CaptchaChallengeEntity::find_by_id((chat_id, user_id as i64))
+            .one(&self.db)
+            .await?
+        else {
+            self.answer_callback(api, query, Some("验证已失效")).await?;
+            return Ok(());
+        };
+        // 超时的验证交给后台任务处理，避免与移出操作同时进行
+        if challenge.expires_at <= chrono::Utc::now().timestamp() {
+            self.answer_callback(api, query, Some("验证已超时")).await?;
+            return Ok(());
+        }
+
+        if answer != challenge.answer {
+            self.answer_callback(api, query, Some("回答错误")).await?;
+            if self.kick_member(api, chat_id, user_id).await {
+                self.finish_captcha_kick(api, &challenge, "入群验证回答错误")
+                    .await?;
+            } else {
+                // 移出失败时将验证标记为超时，由后台任务继续尝试
+                let mut active_model: captcha_challenge::ActiveModel = challenge.into();
+                active_model.expires_at = Set(chrono::Utc::now().timestamp());
+                active_model.update(&self.db).await?;
+            }
+            return Ok(());
+        }
+
+        CaptchaChallengeEntity::delete_by_id((chat_id, user_id as i64))
+            .exec(&self.db)
+            .await?;
+        self.delete_captcha_message(api, &challenge).await;
+
+        self.answer_callback(api, query, Some("验证通过")).await?;
+        self.lift_restriction(api, chat_id, user_id).await?;
+
+        // 开启验证时欢迎消息在通过验证后才发送
+        let setting = self.load_group_setting(chat_id).await?;
+        if let Some(template) = &setting.welcome_message {
+            let text = self.render_placeholders(template, message, Some(&query.from));
+            self.send_reply(api.clone(), chat_id, &text, None).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 将超时未完成验证的成员移出群组，返回移出的数量
+    pub async fn expire_captchas(&self, api: &Bot) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let expired = CaptchaChallengeEntity::find()
+            .filter(captcha_challenge::Column::ExpiresAt.lte(now))
+            .all(&self.db)
+            .await?;
+
+        let mut kicked = 0;
+        for challenge in expired {
+            match self.expire_captcha(api, challenge, now).await {
+                Ok(true) => kicked += 1,
+                Ok(false) => {}
+                Err(e) => eprintln!("处理入群验证超时时出错: {}", e),
+            }
+        }
+
+        Ok(kicked)
+    }
+
+    /// 移出一名超时的成员，移出失败时保留验证记录等待下次重试，返回是否已移出
+    async fn expire_captcha(
+        &self,
+        api: &Bot,
+        challenge: captcha_challenge::Model,
+        now: i64,
+    ) -> Result<bool> {
+        let user_id = challenge.user_id as u64;
+        if self.kick_member(api, challenge.group_id, user_id).await {
+            self.finish_captcha_kick(api, &challenge, "入群验证超时")
+                .await?;
+            return Ok(true);
+        }
+
+        // 禁言到期后成员已经可以发言，不再继续重试
+        if now >= challenge.expires_at + i64::from(CAPTCHA_MUTE_MARGIN_SECS) {
+            eprintln!(
+                "多次移出用户 {} 失败，放弃群组 {} 的入群验证",
+                user_id, challenge.group_id
+            );
+            CaptchaChallengeEntity::delete_by_id((challenge.group_id, challenge.user_id))
+                .exec(&self.db)
+                .await?;
+            self.delete_captcha_message(api, &challenge).await;
+        }
+
+        Ok(false)
+    }
+
+    /// 成员被移出后删除验证记录和验证消息，并写入管理记录
+    async fn finish_captcha_kick(
+        &self,
+        api: &Bot,
+        challenge: &captcha_challenge::Model,
+        reason: &str,
+    ) -> Result<()> {
+        CaptchaChallengeEntity::delete_by_id((challenge.group_id, challenge.user_id))
+            .exec(&self.db)
+            .await?;
+        self.delete_captcha_message(api, challenge).await;
+        self.log_moderation(
+            challenge.group_id,
+            challenge.user_id,
+            "captcha_kick",
+            Some(reason),
+            0,
+        )
+        .await
+    }
+
+    /// 移出成员但不封禁，之后仍可重新加入，返回是否已移出
+    async fn kick_member(&self, api: &Bot, chat_id: i64, user_id: u64) -> bool {
+        let params = BanChatMemberParams::builder()
+            .chat_id(chat_id)
+            .user_id(user_id)
+            .build();
+        if let Err(e) = api.ban_chat_member(&params).await {
+            eprintln!("移出用户 {} 时出错: {}", user_id, e);
+            return false;
+        }
+        let params = UnbanChatMemberParams::builder()
+            .chat_id(chat_id)
+            .user_id(user_id)
+            .only_if_banned(true)
+            .build();
+        if let Err(e) = api.unban_chat_member(&params).await {
+            eprintln!("解除用户 {} 的封禁时出错: {}", user_id, e);
+        }
+
+        true
+    }
+
+    /// 解除验证期间的禁言，恢复为群组的默认权限
+    async fn lift_restriction(&self, api: &Bot, chat_id: i64, user_id: u64) -> Result<()> {
+        let params = GetChatParams::builder().chat_id(chat_id).build();
+        let permissions = match api.get_chat(&params).await {
+            Ok(response) => response.result.permissions,
+            Err(e) => {
+                eprintln!("获取群组默认权限时出错: {}", e);
+                None
+            }
+        };
+        let permissions = permissions.unwrap_or_else(|| {
+            ChatPermissions::builder()
+                .can_send_messages(true)
+                .can_send_audios(true)
+                .can_send_documents(true)
+                .can_send_photos(true)
+                .can_send_videos(true)
+                .can_send_video_notes(true)
+                .can_send_voice_notes(true)
+                .can_send_polls(true)
+                .can_send_other_messages(true)
+                .can_add_web_page_previews(true)
+                .build()
+        });
+
+        let params = RestrictChatMemberParams::builder()
+            .chat_id(chat_id)
+            .user_id(user_id)
+            .permissions(permissions)
+            .build();
+        if let Err(e) = api.restrict_chat_member(&params).await {
+            eprintln!("解除用户 {} 的禁言时出错: {}", user_id, e);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_captcha_message(&self, api: &Bot, challenge: &captcha_challenge::Model) {
+        let Some(message_id) = challenge.message_id else {
+            return;
+        };
+        let params = DeleteMessageParams::builder()
+            .chat_id(challenge.group_id)
+            .message_id(message_id)
+            .build();
+        if let Err(e) = api.delete_message(&params).await {
+            eprintln!("删除入群验证消息时出错: {}", e);
+        }
+    }
+
+    pub(super) async fn handle_captcha_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let usage = "用法: /captcha on|off、/captcha button|math 或 /captcha timeout &lt;时长&gt;";
+        let content = self.get_content_after_command(message, command)?;
+        let args: Vec<&str> = content.split_whitespace().collect();
+
+        let message_text = match args.as_slice() {
+            [] => {
+                let setting = self.load_group_setting(group_id).await?;
+                let mode = setting
+                    .captcha_mode
+                    .parse::<CaptchaMode>()
+                    .unwrap_or(CaptchaMode::Button);
+                let text = format!(
+                    "入群验证: {}\n验证方式: {}\n验证时限: {}\n{}",
+                    if setting.captcha_enabled {
+                        "开启"
+                    } else {
+                        "关闭"
+                    },
+                    mode.label(),
+                    self.format_duration(setting.captcha_timeout_secs.max(0) as u32),
+                    usage
+                );
+                self.send_reply(api, message.chat.id, &text, None).await?;
+                return Ok(());
+            }
+            ["on" | "off"] => {
+                let enabled = args[0] == "on";
+                self.update_group_setting(group_id, |setting| {
+                    setting.captcha_enabled = Set(enabled);
+                })
+                .await?;
+                if !enabled {
+                    "已关闭入群验证，已在验证中的成员仍需完成验证".to_string()
+                } else if !self
+                    .bot_has_right(&api, group_id, AdminRight::RestrictMembers)
+                    .await?
+                {
+                    "已开启入群验证\n注意：机器人当前不具备 <code>can_restrict_members</code> 管理员权限，无法禁言新成员。授予权限后可发送 /reload 立即生效".to_string()
+                } else {
+                    "已开启入群验证，新成员需要在时限内完成验证".to_string()
+                }
+            }
+            ["timeout", duration] => {
+                let Some(secs) = self.parse_duration(duration).filter(|secs| {
+                    (MIN_CAPTCHA_TIMEOUT_SECS..=MAX_CAPTCHA_TIMEOUT_SECS).contains(secs)
+                }) else {
+                    self.send_reply(
+                        api,
+                        message.chat.id,
+                        "验证时限必须在 30 秒到 1 小时之间",
+                        None,
+                    )
+                    .await?;
+                    return Ok(());
+                };
+                self.update_group_setting(group_id, |setting| {
+                    setting.captcha_timeout_secs = Set(secs as i32);
+                })
+                .await?;
+                format!("验证时限已设置为: {}", self.format_duration(secs))
+            }
+            [mode] => {
+                let Ok(mode) = mode.parse::<CaptchaMode>() else {
+                    self.send_reply(api, message.chat.id, usage, None).await?;
+                    return Ok(());
+                };
+                self.update_group_setting(group_id, |setting| {
+                    setting.captcha_mode = Set(mode.as_str().to_string());
+                })
+                .await?;
+                format!("验证方式已设置为: {}", mode.label())
+            }
+            _ => usage.to_string(),
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+use super::audit::Operation;
+use super::permission::Permission;
+use super::transfer::{ImportMode, KeywordRecord};
+use super::{AUTO_DELETE_AFTER_SECS, MessageHandler};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::ParseMode;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::EditMessageTextParams;
+use frankenstein::types::{
+    CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, MaybeInaccessibleMessage, Message,
+};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, TransactionTrait};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+pub(super) const CALLBACK_PREFIX: &str = "confirm";
+
+// 确认按钮的有效期，与确认消息的自动删除时间一致
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(AUTO_DELETE_AFTER_SECS);
+
+/// 需要二次确认的破坏性操作
+pub(super) enum PendingAction {
+    /// 删除群组的全部关键词
+    DeleteAll,
+    /// 以替换模式导入关键词，先清空群组再导入
+    ReplaceImport {
+        records: Vec<KeywordRecord>,
+        allow_actions: bool,
+    },
+}
+
+impl PendingAction {
+    fn permission(&self) -> Permission {
+        match self {
+            PendingAction::DeleteAll => Permission::Moderate,
+            PendingAction::ReplaceImport { .. } => Permission::Edit,
+        }
+    }
+}
+
+struct PendingConfirmation {
+    group_id: i64,
+    actor_id: i64,
+    // 发起确认的用户，匿名管理员发起时为 None，由任意具备权限的用户确认
+    requester: Option<u64>,
+    action: PendingAction,
+    created_at: Instant,
+}
+
+/// 等待用户点击确认按钮的操作，在所有消息处理任务之间共享
+#[derive(Clone, Default)]
+pub(super) struct PendingConfirmations {
+    next_id: Arc<AtomicU64>,
+    entries: Arc<Mutex<HashMap<u64, PendingConfirmation>>>,
+}
+
+impl PendingConfirmations {
+    fn insert(&self, confirmation: PendingConfirmation) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        // 顺便丢弃已过期的确认
+        entries.retain(|_, pending| pending.created_at.elapsed() < CONFIRM_TIMEOUT);
+        entries.insert(id, confirmation);
+        id
+    }
+
+    /// 查询未过期的确认，返回所属群组、发起者和所需权限
+    fn peek(&self, id: u64) -> Option<(i64, Option<u64>, Permission)> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(&id)
+            .filter(|pending| pending.created_at.elapsed() < CONFIRM_TIMEOUT)
+            .map(|pending| {
+                (
+                    pending.group_id,
+                    pending.requester,
+                    pending.action.permission(),
+                )
+            })
+    }
+
+    fn remove(&self, id: u64) -> Option<PendingConfirmation> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .remove(&id)
+            .filter(|pending| pending.created_at.elapsed() < CONFIRM_TIMEOUT)
+    }
+}
+
+impl MessageHandler {
+    /// 发送带有确认和取消按钮的提示，操作在发起者确认后才执行
+    pub(super) async fn request_confirmation(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+        action: PendingAction,
+        text: &str,
+    ) -> Result<()> {
+        let requester = match (&message.sender_chat, &message.from) {
+            (None, Some(from)) => Some(from.id),
+            _ => None,
+        };
+        let id = self.confirmations.insert(PendingConfirmation {
+            group_id,
+            actor_id: self.actor_id(message),
+            requester,
+            action,
+            created_at: Instant::now(),
+        });
+
+        let button = |text: &str, choice: &str| {
+            InlineKeyboardButton::builder()
+                .text(text)
+                .callback_data(format!("{}:{}:{}", CALLBACK_PREFIX, id, choice))
+                .build()
+        };
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(vec![vec![button("确认", "yes"), button("取消", "no")]])
+            .build();
+        let text = format!("{}\n\n请在 {} 秒内确认。", text, CONFIRM_TIMEOUT.as_secs());
+        self.send_reply_with_markup(api, message.chat.id, &text, None, Some(keyboard))
+            .await?;
+
+        Ok(())
+    }
+
+    /// 处理确认按钮，回调数据格式为 `confirm:<ID>:yes|no`
+    pub(super) async fn handle_confirm_callback(
+        &self,
+        api: &Bot,
+        query: &CallbackQuery,
+        args: &str,
+    ) -> Result<()> {
+        let Some(MaybeInaccessibleMessage::Message(message)) = &query.message else {
+            self.answer_callback(api, query, Some("消息已过期")).await?;
+            return Ok(());
+        };
+        let Some((id, confirmed)) = args
+            .split_once(':')
+            .and_then(|(id, choice)| Some((id.parse::<u64>().ok()?, choice == "yes")))
+        else {
+            self.answer_callback(api, query, None).await?;
+            return Ok(());
+        };
+
+        let Some((group_id, requester, permission)) = self.confirmations.peek(id) else {
+            self.answer_callback(api, query, Some("确认已过期，请重新发送命令"))
+                .await?;
+            return Ok(());
+        };
+        let allowed = match requester {
+            Some(requester) => requester == query.from.id,
+            None => {
+                self.has_permission(api, group_id, query.from.id, permission)
+                    .await?
+            }
+        };
+        if !allowed {
+            self.answer_callback(api, query, Some("只有发起者才能确认此操作"))
+                .await?;
+            return Ok(());
+        }
+
+        // 两次点击可能同时到达，只有取走确认的一方执行操作
+        let Some(pending) = self.confirmations.remove(id) else {
+            self.answer_callback(api, query, Some("确认已过期，请重新发送命令"))
+                .await?;
+            return Ok(());
+        };
+        self.answer_callback(api, query, None).await?;
+
+        let text = if !confirmed {
+            "已取消操作".to_string()
+        } else {
+            match self.run_pending_action(pending).await {
+                Ok(text) => text,
+                Err(e) => {
+                    eprintln!("执行已确认的操作时出错: {}", e);
+                    "操作失败，请稍后重试".to_string()
+                }
+            }
+        };
+        let params = EditMessageTextParams::builder()
+            .chat_id(message.chat.id)
+            .message_id(message.message_id)
+            .text(text)
+            .parse_mode(ParseMode::Html)
+            .build();
+        if let Err(e) = api.edit_message_text(&params).await {
+            eprintln!("更新确认消息时出错: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// 执行已确认的操作，返回展示给用户的结果
+    async fn run_pending_action(&self, pending: PendingConfirmation) -> Result<String> {
+        let operation = Operation::new(pending.actor_id);
+        let text = match pending.action {
+            PendingAction::DeleteAll => {
+                let txn = self.db.begin().await?;
+                let count = self
+                    .delete_all_keywords(&txn, pending.group_id, operation)
+                    .await?;
+                txn.commit().await?;
+                format!(
+                    "已删除 {} 个关键词！可使用 /undo 撤销或 /restore 逐个恢复",
+                    count
+                )
+            }
+            PendingAction::ReplaceImport {
+                records,
+                allow_actions,
+            } => {
+                let summary = self
+                    .import_keyword_records(
+                        pending.group_id,
+                        operation,
+                        records,
+                        ImportMode::Replace,
+                        allow_actions,
+                    )
+                    .await?;
+                self.format_import_summary(&summary)
+            }
+        };
+
+        Ok(text)
+    }
+
+    /// 替换模式会清空群组现有的关键词，群组非空时先请求确认
+    pub(super) async fn import_or_confirm(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+        records: Vec<KeywordRecord>,
+        mode: ImportMode,
+        allow_actions: bool,
+    ) -> Result<()> {
+        if mode == ImportMode::Replace {
+            let existing = GroupReplyEntity::find()
+                .filter(group_reply::Column::GroupId.eq(group_id))
+                .count(&self.db)
+                .await?;
+            if existing > 0 {
+                let text = format!(
+                    "将清空当前群组的 {} 个关键词并导入 {} 条记录，确定继续吗？",
+                    existing,
+                    records.len()
+                );
+                return self
+                    .request_confirmation(
+                        api,
+                        message,
+                        group_id,
+                        PendingAction::ReplaceImport {
+                            records,
+                            allow_actions,
+                        },
+                        &text,
+                    )
+                    .await;
+            }
+        }
+
+        let summary = self
+            .import_keyword_records(
+                group_id,
+                self.begin_operation(message),
+                records,
+                mode,
+                allow_actions,
+            )
+            .await?;
+        self.send_reply(
+            api,
+            message.chat.id,
+            &self.format_import_summary(&summary),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
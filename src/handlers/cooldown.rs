@@ -0,0 +1,436 @@
+use super::MessageHandler;
+use crate::entities::group_reply;
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::{ActiveModelTrait, Set};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// 用户触发频率限制的统计窗口
+const RATE_LIMIT_WINDOW_MS: i64 = 60 * 1000;
+
+// 冷却时间的上限，一天
+const MAX_COOLDOWN_SECS: u32 = 24 * 60 * 60;
+
+// 每个用户每分钟触发次数的上限
+const MAX_USER_RATE_LIMIT: u32 = 1000;
+
+#[derive(Default)]
+struct CooldownState {
+    // 每个群组中每个关键词冷却结束的时间，单位毫秒
+    keywords: HashMap<(i64, String), i64>,
+    // 每个群组中每个用户在统计窗口内触发回复的时间
+    users: HashMap<(i64, i64), VecDeque<i64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeywordCooldown {
+    group_id: i64,
+    keywords: String,
+    until: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UserTriggers {
+    group_id: i64,
+    user_id: i64,
+    triggered_at: Vec<i64>,
+}
+
+/// 写入状态文件的冷却数据，JSON 的键只能是字符串，所以保存为列表
+#[derive(Default, Serialize, Deserialize)]
+struct CooldownSnapshot {
+    keywords: Vec<KeywordCooldown>,
+    users: Vec<UserTriggers>,
+}
+
+/// 关键词冷却和用户触发频率的状态，在所有消息处理任务之间共享
+///
+/// 状态只保存在内存中，配置了 `COOLDOWN_STATE_FILE` 时由后台任务定期写入文件，重启后恢复
+#[derive(Clone, Default)]
+pub(super) struct Cooldowns {
+    state: Arc<Mutex<CooldownState>>,
+}
+
+impl Cooldowns {
+    /// 检查关键词冷却和用户频率限制，两者都通过时记录本次触发并返回 true
+    fn try_acquire(
+        &self,
+        group_id: i64,
+        keywords: &str,
+        user_id: i64,
+        cooldown_ms: i64,
+        user_limit: usize,
+        now: i64,
+    ) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let keyword_key = (group_id, keywords.to_string());
+        if cooldown_ms > 0
+            && state
+                .keywords
+                .get(&keyword_key)
+                .is_some_and(|until| *until > now)
+        {
+            return false;
+        }
+
+        if user_limit > 0 {
+            let triggers = state.users.entry((group_id, user_id)).or_default();
+            while triggers
+                .front()
+                .is_some_and(|at| *at <= now - RATE_LIMIT_WINDOW_MS)
+            {
+                triggers.pop_front();
+            }
+            if triggers.len() >= user_limit {
+                return false;
+            }
+            triggers.push_back(now);
+        }
+
+        if cooldown_ms > 0 {
+            state.keywords.insert(keyword_key, now + cooldown_ms);
+        }
+        true
+    }
+
+    /// 丢弃已经结束的冷却和统计窗口之外的触发记录
+    fn prune(&self, now: i64) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.keywords.retain(|_, until| *until > now);
+        state.users.retain(|_, triggers| {
+            triggers.retain(|at| *at > now - RATE_LIMIT_WINDOW_MS);
+            !triggers.is_empty()
+        });
+    }
+
+    fn snapshot(&self) -> CooldownSnapshot {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        CooldownSnapshot {
+            keywords: state
+                .keywords
+                .iter()
+                .map(|((group_id, keywords), until)| KeywordCooldown {
+                    group_id: *group_id,
+                    keywords: keywords.clone(),
+                    until: *until,
+                })
+                .collect(),
+            users: state
+                .users
+                .iter()
+                .map(|((group_id, user_id), triggers)| UserTriggers {
+                    group_id: *group_id,
+                    user_id: *user_id,
+                    triggered_at: triggers.iter().copied().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn restore(&self, snapshot: CooldownSnapshot) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        for cooldown in snapshot.keywords {
+            state
+                .keywords
+                .insert((cooldown.group_id, cooldown.keywords), cooldown.until);
+        }
+        for user in snapshot.users {
+            state
+                .users
+                .insert((user.group_id, user.user_id), user.triggered_at.into());
+        }
+    }
+}
+
+impl MessageHandler {
+    /// 从状态文件恢复冷却数据，文件不存在或无法解析时从空状态开始
+    pub(super) fn load_cooldowns(&self) {
+        let Some(path) = &self.config.cooldown_state_file else {
+            return;
+        };
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                eprintln!("读取冷却状态文件时出错: {}", e);
+                return;
+            }
+        };
+        match serde_json::from_str(&content) {
+            Ok(snapshot) => {
+                self.cooldowns.restore(snapshot);
+                self.cooldowns.prune(chrono::Utc::now().timestamp_millis());
+            }
+            Err(e) => eprintln!("冷却状态文件格式错误: {}", e),
+        }
+    }
+
+    /// 清理过期的冷却数据，配置了状态文件时写入文件
+    pub async fn save_cooldowns(&self) -> Result<()> {
+        self.cooldowns.prune(chrono::Utc::now().timestamp_millis());
+        let Some(path) = &self.config.cooldown_state_file else {
+            return Ok(());
+        };
+
+        // 先写入临时文件再替换，避免写到一半时退出导致文件损坏
+        let content = serde_json::to_string(&self.cooldowns.snapshot())?;
+        let temp_path = format!("{}.tmp", path);
+        tokio::fs::write(&temp_path, content).await?;
+        tokio::fs::rename(&temp_path, path).await?;
+
+        Ok(())
+    }
+
+    /// 检查关键词是否可以回复，关键词自身的冷却时间优先于群组设置
+    pub(super) async fn check_cooldown(
+        &self,
+        message: &Message,
+        reply: &group_reply::Model,
+    ) -> Result<bool> {
+        let setting = self.load_group_setting(message.chat.id).await?;
+        let cooldown_secs = reply.cooldown_secs.unwrap_or(setting.keyword_cooldown_secs);
+        if cooldown_secs <= 0 && setting.user_rate_limit <= 0 {
+            return Ok(true);
+        }
+
+        Ok(self.cooldowns.try_acquire(
+            message.chat.id,
+            &reply.keywords,
+            self.actor_id(message),
+            i64::from(cooldown_secs.max(0)) * 1000,
+            usize::try_from(setting.user_rate_limit).unwrap_or_default(),
+            chrono::Utc::now().timestamp_millis(),
+        ))
+    }
+
+    pub(super) async fn handle_cooldown_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let usage = "用法: /cooldown &lt;秒数&gt; 或 /cooldown &lt;关键词&gt; &lt;秒数|default&gt;";
+        let content = self.get_content_after_command(message, command)?;
+        let args: Vec<&str> = content.split_whitespace().collect();
+
+        match args.as_slice() {
+            [] => {
+                let setting = self.load_group_setting(group_id).await?;
+                let text = format!(
+                    "关键词冷却时间: {}\n每人每分钟触发上限: {}\n{}",
+                    self.format_cooldown(setting.keyword_cooldown_secs),
+                    self.format_rate_limit(setting.user_rate_limit),
+                    usage
+                );
+                self.send_reply(api, message.chat.id, &text, None).await?;
+            }
+            [secs] => {
+                let Some(secs) = self.parse_cooldown_secs(secs) else {
+                    self.send_reply(api, message.chat.id, usage, None).await?;
+                    return Ok(());
+                };
+                self.update_group_setting(group_id, |setting| {
+                    setting.keyword_cooldown_secs = Set(secs);
+                })
+                .await?;
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!(
+                        "本群关键词的冷却时间已设置为: {}",
+                        self.format_cooldown(secs)
+                    ),
+                    None,
+                )
+                .await?;
+            }
+            [keywords, secs] => {
+                let secs = match *secs {
+                    "default" => None,
+                    secs => match self.parse_cooldown_secs(secs) {
+                        Some(secs) => Some(secs),
+                        None => {
+                            self.send_reply(api, message.chat.id, usage, None).await?;
+                            return Ok(());
+                        }
+                    },
+                };
+
+                let keywords = self.resolve_keyword(group_id, keywords).await?;
+                let Some(existing) = self.find_keyword_reply(group_id, &keywords).await? else {
+                    self.send_reply(
+                        api,
+                        message.chat.id,
+                        &format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords)),
+                        None,
+                    )
+                    .await?;
+                    return Ok(());
+                };
+                let mut active_model: group_reply::ActiveModel = existing.into();
+                active_model.cooldown_secs = Set(secs);
+                active_model.update(&self.db).await?;
+
+                let message_text = match secs {
+                    Some(secs) => format!(
+                        "关键词 <code>{}</code> 的冷却时间已设置为: {}",
+                        self.escape_html(&keywords),
+                        self.format_cooldown(secs)
+                    ),
+                    None => format!(
+                        "关键词 <code>{}</code> 已恢复使用本群的冷却时间",
+                        self.escape_html(&keywords)
+                    ),
+                };
+                self.send_reply(api, message.chat.id, &message_text, None)
+                    .await?;
+            }
+            _ => {
+                self.send_reply(api, message.chat.id, usage, None).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_ratelimit_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let limit = match content.trim() {
+            "" => {
+                let setting = self.load_group_setting(group_id).await?;
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!(
+                        "每人每分钟触发上限: {}\n用法: /ratelimit &lt;次数&gt;，0 表示不限制",
+                        self.format_rate_limit(setting.user_rate_limit)
+                    ),
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+            value => match value.parse::<u32>() {
+                Ok(limit) if limit <= MAX_USER_RATE_LIMIT => limit as i32,
+                _ => {
+                    self.send_reply(
+                        api,
+                        message.chat.id,
+                        &format!(
+                            "用法: /ratelimit &lt;次数&gt;，0 表示不限制，最大 {}",
+                            MAX_USER_RATE_LIMIT
+                        ),
+                        None,
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+        };
+
+        self.update_group_setting(group_id, |setting| {
+            setting.user_rate_limit = Set(limit);
+        })
+        .await?;
+        self.send_reply(
+            api,
+            message.chat.id,
+            &format!(
+                "每人每分钟触发上限已设置为: {}",
+                self.format_rate_limit(limit)
+            ),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    fn parse_cooldown_secs(&self, value: &str) -> Option<i32> {
+        value
+            .parse::<u32>()
+            .ok()
+            .filter(|secs| *secs <= MAX_COOLDOWN_SECS)
+            .map(|secs| secs as i32)
+    }
+
+    pub(super) fn format_cooldown(&self, secs: i32) -> String {
+        if secs <= 0 {
+            "不限制".to_string()
+        } else {
+            format!("{} 秒", secs)
+        }
+    }
+
+    fn format_rate_limit(&self, limit: i32) -> String {
+        if limit <= 0 {
+            "不限制".to_string()
+        } else {
+            format!("{} 次", limit)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cooldowns, RATE_LIMIT_WINDOW_MS};
+
+    #[test]
+    fn keyword_cooldown_blocks_until_expiry() {
+        let cooldowns = Cooldowns::default();
+        assert!(cooldowns.try_acquire(-100, "群规", 1, 10_000, 0, 0));
+        assert!(!cooldowns.try_acquire(-100, "群规", 2, 10_000, 0, 9_999));
+        assert!(cooldowns.try_acquire(-100, "群规", 2, 10_000, 0, 10_000));
+        // 冷却按群组和关键词分别计算
+        assert!(cooldowns.try_acquire(-100, "价格", 1, 10_000, 0, 10_001));
+        assert!(cooldowns.try_acquire(-200, "群规", 1, 10_000, 0, 10_001));
+    }
+
+    #[test]
+    fn user_limit_window_slides_after_a_minute() {
+        let cooldowns = Cooldowns::default();
+        assert!(cooldowns.try_acquire(-100, "群规", 1, 0, 2, 0));
+        assert!(cooldowns.try_acquire(-100, "价格", 1, 0, 2, 30_000));
+        assert!(!cooldowns.try_acquire(-100, "群规", 1, 0, 2, 59_999));
+        // 其他用户不受影响
+        assert!(cooldowns.try_acquire(-100, "群规", 2, 0, 2, 59_999));
+        // 第一次触发移出窗口后可以再触发一次，第二次仍在窗口内
+        assert!(cooldowns.try_acquire(-100, "群规", 1, 0, 2, RATE_LIMIT_WINDOW_MS));
+        assert!(!cooldowns.try_acquire(-100, "群规", 1, 0, 2, RATE_LIMIT_WINDOW_MS + 1));
+    }
+
+    #[test]
+    fn user_limit_rejection_does_not_start_keyword_cooldown() {
+        let cooldowns = Cooldowns::default();
+        assert!(cooldowns.try_acquire(-100, "价格", 1, 0, 1, 0));
+        assert!(!cooldowns.try_acquire(-100, "群规", 1, 10_000, 1, 1_000));
+        assert!(cooldowns.try_acquire(-100, "群规", 2, 10_000, 1, 2_000));
+    }
+
+    #[test]
+    fn prune_drops_expired_state() {
+        let cooldowns = Cooldowns::default();
+        assert!(cooldowns.try_acquire(-100, "群规", 1, 10_000, 5, 0));
+        cooldowns.prune(RATE_LIMIT_WINDOW_MS);
+        let snapshot = cooldowns.snapshot();
+        assert!(snapshot.keywords.is_empty());
+        assert!(snapshot.users.is_empty());
+
+        assert!(cooldowns.try_acquire(-100, "群规", 1, 120_000, 5, 70_000));
+        cooldowns.prune(100_000);
+        let snapshot = cooldowns.snapshot();
+        assert_eq!(snapshot.keywords.len(), 1);
+        assert_eq!(snapshot.users.len(), 1);
+    }
+}
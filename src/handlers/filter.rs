@@ -0,0 +1,290 @@
+use super::MessageHandler;
+use crate::entities::group_setting;
+use crate::entities::ignored_user::{self, Entity as IgnoredUserEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::{Message, MessageEntityType};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use std::str::FromStr;
+
+/// 可按群组开关的消息过滤条件，命中的消息不会触发关键词回复
+#[derive(Clone, Copy)]
+enum MessageFilter {
+    /// 其他机器人发送的消息
+    Bots,
+    /// 从其他聊天转发的消息
+    Forwards,
+    /// 关联频道自动转发到讨论组的消息
+    ChannelPosts,
+}
+
+impl MessageFilter {
+    const ALL: [MessageFilter; 3] = [
+        MessageFilter::Bots,
+        MessageFilter::Forwards,
+        MessageFilter::ChannelPosts,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageFilter::Bots => "bots",
+            MessageFilter::Forwards => "forwards",
+            MessageFilter::ChannelPosts => "channel",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            MessageFilter::Bots => "机器人发送的消息",
+            MessageFilter::Forwards => "转发的消息",
+            MessageFilter::ChannelPosts => "关联频道的帖子",
+        }
+    }
+
+    fn is_enabled(self, setting: &group_setting::Model) -> bool {
+        match self {
+            MessageFilter::Bots => setting.ignore_bots,
+            MessageFilter::Forwards => setting.ignore_forwards,
+            MessageFilter::ChannelPosts => setting.ignore_channel_posts,
+        }
+    }
+
+    fn set_enabled(self, setting: &mut group_setting::ActiveModel, enabled: bool) {
+        match self {
+            MessageFilter::Bots => setting.ignore_bots = Set(enabled),
+            MessageFilter::Forwards => setting.ignore_forwards = Set(enabled),
+            MessageFilter::ChannelPosts => setting.ignore_channel_posts = Set(enabled),
+        }
+    }
+
+    fn matches(self, message: &Message) -> bool {
+        match self {
+            // 匿名管理员的 from 为 GroupAnonymousBot，同样是机器人，需要排除
+            MessageFilter::Bots => {
+                message.sender_chat.is_none()
+                    && message.from.as_ref().is_some_and(|from| from.is_bot)
+            }
+            MessageFilter::Forwards => message.forward_origin.is_some(),
+            MessageFilter::ChannelPosts => message.is_automatic_forward == Some(true),
+        }
+    }
+}
+
+impl FromStr for MessageFilter {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MessageFilter::ALL
+            .into_iter()
+            .find(|filter| filter.as_str() == s)
+            .ok_or(())
+    }
+}
+
+impl MessageHandler {
+    /// 检查消息是否命中群组开启的过滤条件或来自忽略列表中的用户
+    pub(super) async fn should_ignore(&self, message: &Message) -> Result<bool> {
+        let setting = self.load_group_setting(message.chat.id).await?;
+        if MessageFilter::ALL
+            .into_iter()
+            .any(|filter| filter.is_enabled(&setting) && filter.matches(message))
+        {
+            return Ok(true);
+        }
+
+        let ignored = IgnoredUserEntity::find_by_id((message.chat.id, self.actor_id(message)))
+            .one(&self.db)
+            .await?
+            .is_some();
+
+        Ok(ignored)
+    }
+
+    pub(super) async fn handle_filter_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let usage = "用法: /filter bots|forwards|channel on|off";
+        let content = self.get_content_after_command(message, command)?;
+        let args: Vec<&str> = content.split_whitespace().collect();
+
+        let (filter, enabled) = match args.as_slice() {
+            [] => {
+                let setting = self.load_group_setting(group_id).await?;
+                let mut text = "<b>当前群组的消息过滤:</b>\n".to_string();
+                for filter in MessageFilter::ALL {
+                    let status = if filter.is_enabled(&setting) {
+                        "忽略"
+                    } else {
+                        "回复"
+                    };
+                    text.push_str(&format!(
+                        "<code>{}</code> {}: {}\n",
+                        filter.as_str(),
+                        filter.label(),
+                        status
+                    ));
+                }
+                text.push('\n');
+                text.push_str(usage);
+                self.send_reply(api, message.chat.id, &text, None).await?;
+                return Ok(());
+            }
+            [filter, enabled] => {
+                let enabled = match *enabled {
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        self.send_reply(api, message.chat.id, usage, None).await?;
+                        return Ok(());
+                    }
+                };
+                let Ok(filter) = filter.parse::<MessageFilter>() else {
+                    self.send_reply(api, message.chat.id, usage, None).await?;
+                    return Ok(());
+                };
+                (filter, enabled)
+            }
+            _ => {
+                self.send_reply(api, message.chat.id, usage, None).await?;
+                return Ok(());
+            }
+        };
+
+        self.update_group_setting(group_id, |setting| {
+            filter.set_enabled(setting, enabled);
+        })
+        .await?;
+
+        let message_text = if enabled {
+            format!("已开启过滤，{}不再触发关键词回复", filter.label())
+        } else {
+            format!("已关闭过滤，{}会正常触发关键词回复", filter.label())
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_ignore_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let target = content.trim();
+
+        // 不带参数且没有回复或提及用户时列出忽略列表
+        let has_mention = message
+            .entities
+            .iter()
+            .flatten()
+            .any(|entity| entity.type_field == MessageEntityType::TextMention);
+        if target.is_empty() && !has_mention && self.replied_user(&api, message).await?.is_none() {
+            return self.show_ignored_users(api, message, group_id).await;
+        }
+
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, target)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let existing = IgnoredUserEntity::find_by_id((group_id, target.user_id as i64))
+            .one(&self.db)
+            .await?;
+        if let Some(existing) = existing {
+            if target.username.is_some() {
+                let mut active_model: ignored_user::ActiveModel = existing.into();
+                active_model.username = Set(target.username.clone());
+                active_model.update(&self.db).await?;
+            }
+        } else {
+            let ignored = ignored_user::ActiveModel {
+                group_id: Set(group_id),
+                user_id: Set(target.user_id as i64),
+                username: Set(target.username.clone()),
+            };
+            ignored.insert(&self.db).await?;
+        }
+
+        self.send_reply(
+            api,
+            message.chat.id,
+            &format!(
+                "已忽略 {}，其消息不再触发关键词回复",
+                self.format_user(target.user_id as i64, target.username.as_deref())
+            ),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_unignore_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, content.trim())
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let result = IgnoredUserEntity::delete_by_id((group_id, target.user_id as i64))
+            .exec(&self.db)
+            .await?;
+        let user = self.format_user(target.user_id as i64, target.username.as_deref());
+        let message_text = if result.rows_affected > 0 {
+            format!("已取消忽略 {}", user)
+        } else {
+            format!("{} 不在忽略列表中", user)
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn show_ignored_users(&self, api: Bot, message: &Message, group_id: i64) -> Result<()> {
+        let ignored = IgnoredUserEntity::find()
+            .filter(ignored_user::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?;
+        if ignored.is_empty() {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "忽略列表为空\n用法: /ignore &lt;@用户名|用户ID&gt;，也可以回复用户的消息使用",
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut text = "<b>忽略列表:</b>\n".to_string();
+        for user in ignored {
+            text.push_str(&format!(
+                "{}\n",
+                self.format_user(user.user_id, user.username.as_deref())
+            ));
+        }
+        self.send_reply(api, message.chat.id, text.trim_end(), None)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use super::MessageHandler;
+use super::keyword_list::{ListKind, ListSort};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::Set;
+
+impl MessageHandler {
+    /// 列出全局关键词，仅限机器人所有者
+    pub(super) async fn handle_gall_command(&self, api: Bot, message: &Message) -> Result<()> {
+        let chat_id = message.chat.id;
+        match self
+            .render_list_page(chat_id, ListKind::Global, ListSort::Default, 0, "")
+            .await?
+        {
+            Some(page) => {
+                self.send_reply_with_markup(api, chat_id, &page.text, None, page.keyboard)
+                    .await?;
+            }
+            None => {
+                self.send_reply(api, chat_id, "还没有设置任何全局关键词。", None)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 群组启用或关闭全局关键词，关闭后本群不再匹配任何全局关键词
+    pub(super) async fn handle_global_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let disabled = match content.trim() {
+            "on" => false,
+            "off" => true,
+            "" => {
+                let setting = self.load_group_setting(group_id).await?;
+                let status = if setting.global_disabled {
+                    "已关闭"
+                } else {
+                    "已启用"
+                };
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!("当前群组的全局关键词{}\n用法: /global on|off", status),
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+            _ => {
+                self.send_reply(api, message.chat.id, "用法: /global on|off", None)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        self.update_group_setting(group_id, |setting| {
+            setting.global_disabled = Set(disabled);
+        })
+        .await?;
+
+        let message_text = if disabled {
+            "已关闭全局关键词，本群只匹配自己的关键词"
+        } else {
+            "已启用全局关键词，本群的同名关键词优先生效"
+        };
+        self.send_reply(api, message.chat.id, message_text, None)
+            .await?;
+
+        Ok(())
+    }
+}
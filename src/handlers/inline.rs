@@ -0,0 +1,211 @@
+use super::MessageHandler;
+use crate::entities::group_reply;
+use crate::matcher::{self, MatchStatus};
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::ParseMode;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::inline_mode::{
+    InlineQuery, InlineQueryResult, InlineQueryResultArticle, InputMessageContent,
+    InputTextMessageContent,
+};
+use frankenstein::methods::AnswerInlineQueryParams;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+// Telegram 每次内联查询最多返回 50 条结果
+const MAX_INLINE_RESULTS: usize = 50;
+// 结果因用户所在群组而异，只在客户端短暂缓存
+const INLINE_CACHE_SECS: u32 = 30;
+// 结果描述中显示的回复内容长度
+const DESCRIPTION_CHARS: usize = 80;
+// 用户可搜索群组的缓存时间，避免每次输入都逐个查询群组成员身份
+const INLINE_GROUPS_TTL: Duration = Duration::from_secs(300);
+// 缓存未命中时同时检查成员身份的群组数量，避免一次发出过多请求触发限流
+const MAX_CONCURRENT_GROUP_CHECKS: usize = 8;
+
+/// 各用户内联查询可搜索群组的缓存，在所有处理任务之间共享
+#[derive(Clone, Default)]
+pub(super) struct InlineGroupCache {
+    entries: Arc<Mutex<HashMap<u64, CachedGroups>>>,
+}
+
+struct CachedGroups {
+    fetched_at: Instant,
+    groups: Arc<BTreeSet<i64>>,
+}
+
+impl InlineGroupCache {
+    fn get(&self, user_id: u64) -> Option<Arc<BTreeSet<i64>>> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(&user_id)
+            .filter(|cached| cached.fetched_at.elapsed() < INLINE_GROUPS_TTL)
+            .map(|cached| cached.groups.clone())
+    }
+
+    fn insert(&self, user_id: u64, groups: Arc<BTreeSet<i64>>) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        // 顺便清理过期条目，避免缓存随用户数量无限增长
+        entries.retain(|_, cached| cached.fetched_at.elapsed() < INLINE_GROUPS_TTL);
+        entries.insert(
+            user_id,
+            CachedGroups {
+                fetched_at: Instant::now(),
+                groups,
+            },
+        );
+    }
+}
+
+impl MessageHandler {
+    /// 处理 `@机器人 关键词` 形式的内联查询，返回可直接发送的已存回复
+    pub async fn handle_inline_query(&self, api: Bot, query: InlineQuery) -> Result<()> {
+        let term = query.query.trim().to_lowercase();
+
+        let mut candidates: Vec<(u32, group_reply::Model)> = Vec::new();
+        if !term.is_empty() {
+            let mut seen = HashSet::new();
+            for &group_id in self.inline_groups(&api, query.from.id).await?.iter() {
+                let (replies, aliases) = self.load_effective_keywords(group_id).await?;
+
+                // 查询文本会触发的关键词排在最前，与群组中的实际回复规则一致
+                let triggered: HashSet<&str> = matcher::find_matches(&replies, &aliases, &term)
+                    .into_iter()
+                    .filter(|keyword_match| keyword_match.status == MatchStatus::Matched)
+                    .map(|keyword_match| keyword_match.reply.keywords.as_str())
+                    .collect();
+
+                for reply in &replies {
+                    // 订阅和全局关键词可能在多个群组中重复出现
+                    if !seen.insert((reply.group_id, reply.keywords.clone())) {
+                        continue;
+                    }
+
+                    let score = if triggered.contains(reply.keywords.as_str()) {
+                        Some(0)
+                    } else {
+                        std::iter::once(&reply.keywords)
+                            .chain(aliases.get(&reply.keywords).into_iter().flatten())
+                            .filter_map(|trigger| self.fuzzy_score(&term, &trigger.to_lowercase()))
+                            .min()
+                            .map(|score| score + 1)
+                    };
+                    if let Some(score) = score {
+                        candidates.push((score, reply.clone()));
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by_key(|(score, _)| *score);
+        let results = candidates
+            .into_iter()
+            .take(MAX_INLINE_RESULTS)
+            .enumerate()
+            .map(|(index, (_, reply))| {
+                // 内联结果由查询者本人发送，占位符按查询者替换，无法确定发送到的群组
+                let text = self.render_user_placeholders(&reply.reply, &query.from, "");
+                let content = InputTextMessageContent::builder()
+                    .message_text(text.clone())
+                    .parse_mode(ParseMode::Html)
+                    .build();
+                InlineQueryResult::Article(
+                    InlineQueryResultArticle::builder()
+                        .id(index.to_string())
+                        .title(reply.keywords.clone())
+                        .input_message_content(InputMessageContent::Text(content))
+                        .description(self.inline_description(&text))
+                        .build(),
+                )
+            })
+            .collect();
+
+        let params = AnswerInlineQueryParams::builder()
+            .inline_query_id(query.id)
+            .results(results)
+            .cache_time(INLINE_CACHE_SECS)
+            .is_personal(true)
+            .build();
+        if let Err(e) = api.answer_inline_query(&params).await {
+            eprintln!("应答内联查询时出错: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// 内联查询可搜索的群组：用户所在的已知群组，以及配置的默认群组
+    ///
+    /// 开启了 `/restrict_list` 的群组只对拥有角色的用户开放，结果按用户缓存
+    async fn inline_groups(&self, api: &Bot, user_id: u64) -> Result<Arc<BTreeSet<i64>>> {
+        if let Some(groups) = self.inline_groups.get(user_id) {
+            return Ok(groups);
+        }
+
+        let mut groups: BTreeSet<i64> = self.config.inline_group_ids.iter().copied().collect();
+
+        // 并发检查各群组，单个群组出错时视为用户不在该群组中
+        let mut checks = JoinSet::new();
+        let mut results = Vec::new();
+        for group_id in self.known_groups().await? {
+            if groups.contains(&group_id) {
+                continue;
+            }
+            if checks.len() >= MAX_CONCURRENT_GROUP_CHECKS
+                && let Some(result) = checks.join_next().await
+            {
+                results.push(result);
+            }
+            let handler = self.clone();
+            let api = api.clone();
+            checks.spawn(async move {
+                let visible = handler.is_chat_member(&api, group_id, user_id).await?
+                    && handler.can_view(&api, group_id, user_id).await?;
+                Ok::<_, anyhow::Error>(visible.then_some(group_id))
+            });
+        }
+        while let Some(result) = checks.join_next().await {
+            results.push(result);
+        }
+
+        for result in results {
+            match result
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result)
+            {
+                Ok(Some(group_id)) => {
+                    groups.insert(group_id);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("检查内联查询可搜索的群组时出错: {}", e),
+            }
+        }
+
+        let groups = Arc::new(groups);
+        self.inline_groups.insert(user_id, groups.clone());
+        Ok(groups)
+    }
+
+    /// 去掉 HTML 标签后截取回复开头作为结果描述
+    fn inline_description(&self, reply: &str) -> String {
+        let mut plain = String::new();
+        let mut in_tag = false;
+        for c in reply.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                _ if !in_tag => plain.push(c),
+                _ => {}
+            }
+        }
+        let plain = plain
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&");
+
+        plain.chars().take(DESCRIPTION_CHARS).collect()
+    }
+}
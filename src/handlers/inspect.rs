@@ -0,0 +1,305 @@
+use super::keyword_list::{ListKind, ListSort};
+use super::{GLOBAL_GROUP_ID, MessageHandler};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::matcher::{self, MatchStatus};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+// 搜索词需要写入按钮的回调数据，回调数据总长不能超过 64 字节
+const MAX_SEARCH_TERM_BYTES: usize = 32;
+
+// 仅回复内容匹配时的分数，排在所有关键词匹配之后
+const FUZZY_REPLY_SCORE: u32 = 4;
+
+impl MessageHandler {
+    pub(super) async fn handle_get_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let keywords = content.trim();
+        if keywords.is_empty() {
+            self.send_reply(api, message.chat.id, "用法: /get &lt;关键词&gt;", None)
+                .await?;
+            return Ok(());
+        }
+
+        let keywords = self.resolve_keyword(group_id, keywords).await?;
+        let Some(reply) = self.find_keyword_reply(group_id, &keywords).await? else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords)),
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let aliases = self
+            .load_aliases(group_id)
+            .await?
+            .remove(&reply.keywords)
+            .unwrap_or_default();
+
+        let mut text = format!(
+            "<b>关键词:</b> <code>{}</code>\n",
+            self.escape_html(&reply.keywords)
+        );
+        if !aliases.is_empty() {
+            let aliases: Vec<String> = aliases
+                .iter()
+                .map(|alias| format!("<code>{}</code>", self.escape_html(alias)))
+                .collect();
+            text.push_str(&format!("<b>别名:</b> {}\n", aliases.join(", ")));
+        }
+        text.push_str("<b>匹配方式:</b> 包含匹配，消息与关键词完全相同时触发消息也会被自动删除\n");
+        if let Some(words) = &reply.exclude_words {
+            text.push_str(&format!(
+                "<b>排除词:</b> <code>{}</code>\n",
+                self.escape_html(words)
+            ));
+        }
+        if let Some(words) = &reply.require_words {
+            text.push_str(&format!(
+                "<b>附加条件:</b> <code>{}</code>\n",
+                self.escape_html(words)
+            ));
+        }
+        if let Some(action) = self.describe_keyword_action(&reply) {
+            text.push_str(&format!("<b>触发动作:</b> {}\n", action));
+        }
+        if let Some(secs) = reply.cooldown_secs {
+            text.push_str(&format!(
+                "<b>冷却时间:</b> {}\n",
+                self.format_cooldown(secs)
+            ));
+        }
+        text.push_str(&format!(
+            "<b>原始回复内容:</b>\n<pre>{}</pre>",
+            self.escape_html(&reply.reply)
+        ));
+
+        self.send_reply(api, message.chat.id, &text, None).await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_search_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let term = content.trim();
+        if term.is_empty() {
+            self.send_reply(api, message.chat.id, "用法: /search &lt;搜索词&gt;", None)
+                .await?;
+            return Ok(());
+        }
+        if term.len() > MAX_SEARCH_TERM_BYTES {
+            self.send_reply(api, message.chat.id, "搜索词过长，请缩短后重试", None)
+                .await?;
+            return Ok(());
+        }
+
+        match self
+            .render_list_page(group_id, ListKind::Search, ListSort::Default, 0, term)
+            .await?
+        {
+            Some(page) => {
+                self.send_reply_with_markup(api, message.chat.id, &page.text, None, page.keyboard)
+                    .await?;
+            }
+            None => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!(
+                        "没有找到与 <code>{}</code> 相关的关键词",
+                        self.escape_html(term)
+                    ),
+                    None,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 试运行关键词匹配，列出所有命中的关键词及实际会发送的回复
+    pub(super) async fn handle_test_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let text = content.trim();
+        if text.is_empty() {
+            self.send_reply(api, message.chat.id, "用法: /test &lt;示例文本&gt;", None)
+                .await?;
+            return Ok(());
+        }
+
+        let (replies, aliases) = self.load_effective_keywords(group_id).await?;
+        let matches = matcher::find_matches(&replies, &aliases, text);
+
+        if matches.is_empty() {
+            self.send_reply(api, message.chat.id, "没有关键词会被触发", None)
+                .await?;
+            return Ok(());
+        }
+
+        let mut result = "<b>按优先级排列的命中关键词:</b>\n".to_string();
+        for (index, keyword_match) in matches.iter().enumerate() {
+            let status = match keyword_match.status {
+                MatchStatus::Matched => "命中",
+                MatchStatus::Excluded => "包含排除词，跳过",
+                MatchStatus::MissingRequired => "不满足附加条件，跳过",
+            };
+            result.push_str(&format!(
+                "{}. <code>{}</code>",
+                index + 1,
+                self.escape_html(&keyword_match.reply.keywords)
+            ));
+            if keyword_match.reply.group_id == GLOBAL_GROUP_ID {
+                result.push_str("（全局）");
+            } else if keyword_match.reply.group_id != group_id {
+                result.push_str("（继承）");
+            }
+            if keyword_match.trigger != keyword_match.reply.keywords {
+                result.push_str(&format!(
+                    "（别名 <code>{}</code>）",
+                    self.escape_html(keyword_match.trigger)
+                ));
+            }
+            result.push_str(&format!(" - {}\n", status));
+        }
+
+        match matcher::first_match(&matches) {
+            Some(keyword_match) => {
+                let reply = self.render_placeholders(&keyword_match.reply.reply, message, None);
+                result.push_str(&format!(
+                    "\n<b>将发送的回复</b>（来自 <code>{}</code>）:\n{}",
+                    self.escape_html(&keyword_match.reply.keywords),
+                    reply
+                ));
+                if keyword_match.exact {
+                    result.push_str("\n\n消息与触发词完全相同，触发消息也会被自动删除");
+                }
+            }
+            None => result.push_str("\n所有命中的关键词均被条件过滤，不会发送回复"),
+        }
+
+        self.send_reply(api, message.chat.id, &result, None).await?;
+
+        Ok(())
+    }
+
+    /// 在关键词、别名和回复内容中模糊搜索，按匹配程度排序后生成列表行
+    pub(super) async fn search_list_lines(
+        &self,
+        chat_id: i64,
+        term: &str,
+        sort: ListSort,
+    ) -> Result<Vec<String>> {
+        let replies = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(chat_id))
+            .all(&self.db)
+            .await?;
+        let mut aliases = self.load_aliases(chat_id).await?;
+
+        let term = term.to_lowercase();
+        let mut matches: Vec<(u32, String, String)> = Vec::new();
+        for reply in replies {
+            let mut triggers = vec![reply.keywords.clone()];
+            triggers.extend(aliases.remove(&reply.keywords).unwrap_or_default());
+
+            let keyword_score = triggers
+                .iter()
+                .filter_map(|trigger| self.fuzzy_score(&term, &trigger.to_lowercase()))
+                .min();
+            let reply_score = reply
+                .reply
+                .to_lowercase()
+                .contains(&term)
+                .then_some(FUZZY_REPLY_SCORE);
+
+            let Some(score) = keyword_score.into_iter().chain(reply_score).min() else {
+                continue;
+            };
+
+            let mut line = format!("<code>{}</code>", self.escape_html(&reply.keywords));
+            if triggers.len() > 1 {
+                let aliases: Vec<String> = triggers[1..]
+                    .iter()
+                    .map(|alias| format!("<code>{}</code>", self.escape_html(alias)))
+                    .collect();
+                line.push_str(&format!(" | {}", aliases.join(" | ")));
+            }
+            if keyword_score.is_none() {
+                line.push_str(" （回复内容匹配）");
+            }
+            matches.push((score, reply.keywords, line));
+        }
+
+        match sort {
+            ListSort::Default => matches.sort_by_key(|(score, _, _)| *score),
+            ListSort::Name => matches.sort_by(|a, b| a.1.cmp(&b.1)),
+        }
+
+        Ok(matches.into_iter().map(|(_, _, line)| line).collect())
+    }
+
+    /// 计算搜索词与候选文本的匹配分数，分数越低越相关，不匹配时返回 None
+    ///
+    /// 完全相同 < 前缀 < 包含 < 按顺序包含搜索词的所有字符
+    pub(super) fn fuzzy_score(&self, term: &str, candidate: &str) -> Option<u32> {
+        if candidate == term {
+            return Some(0);
+        }
+        if candidate.starts_with(term) {
+            return Some(1);
+        }
+        if candidate.contains(term) {
+            return Some(2);
+        }
+
+        let mut chars = candidate.chars();
+        let is_subsequence = term
+            .chars()
+            .all(|c| chars.any(|candidate_char| candidate_char == c));
+        is_subsequence.then_some(3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_handler;
+
+    #[test]
+    fn fuzzy_score_ranks_closer_matches_lower() {
+        let handler = test_handler();
+        assert_eq!(handler.fuzzy_score("群规", "群规"), Some(0));
+        assert_eq!(handler.fuzzy_score("群规", "群规说明"), Some(1));
+        assert_eq!(handler.fuzzy_score("群规", "本群群规"), Some(2));
+        assert_eq!(handler.fuzzy_score("群规", "群组规则"), Some(3));
+    }
+
+    #[test]
+    fn fuzzy_score_requires_characters_in_order() {
+        let handler = test_handler();
+        assert_eq!(handler.fuzzy_score("规群", "群组规则"), None);
+        assert_eq!(handler.fuzzy_score("价格", "群规"), None);
+    }
+}
@@ -0,0 +1,415 @@
+use super::permission::Role;
+use super::{GLOBAL_GROUP_ID, MessageHandler};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::ParseMode;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::EditMessageTextParams;
+use frankenstein::types::{
+    CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, MaybeInaccessibleMessage,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use std::str::FromStr;
+
+pub(super) const CALLBACK_PREFIX: &str = "list";
+
+// 每页最多显示的条目数，以及单页文本的长度上限（Telegram 限制为 4096 字符）
+const ITEMS_PER_PAGE: usize = 30;
+const PAGE_TEXT_LIMIT: usize = 3500;
+
+/// 列表的排序方式
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(super) enum ListSort {
+    /// 按添加顺序
+    #[default]
+    Default,
+    /// 按名称排序
+    Name,
+}
+
+impl ListSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            ListSort::Default => "default",
+            ListSort::Name => "name",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ListSort::Default => "添加顺序",
+            ListSort::Name => "按名称",
+        }
+    }
+}
+
+impl FromStr for ListSort {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(ListSort::Default),
+            "name" => Ok(ListSort::Name),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 可分页显示的列表种类，回调数据中以名称区分
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum ListKind {
+    All,
+    Search,
+    Global,
+    History,
+    Trash,
+}
+
+impl ListKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ListKind::All => "all",
+            ListKind::Search => "search",
+            ListKind::Global => "global",
+            ListKind::History => "history",
+            ListKind::Trash => "trash",
+        }
+    }
+}
+
+impl FromStr for ListKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(ListKind::All),
+            "search" => Ok(ListKind::Search),
+            "global" => Ok(ListKind::Global),
+            "history" => Ok(ListKind::History),
+            "trash" => Ok(ListKind::Trash),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 渲染完成的一页列表
+pub(super) struct ListPage {
+    pub text: String,
+    pub keyboard: Option<InlineKeyboardMarkup>,
+}
+
+impl MessageHandler {
+    pub(super) async fn show_all_keywords(
+        &self,
+        api: Bot,
+        chat_id: i64,
+        group_id: i64,
+        sort: ListSort,
+    ) -> Result<()> {
+        match self
+            .render_list_page(group_id, ListKind::All, sort, 0, "")
+            .await?
+        {
+            Some(page) => {
+                self.send_reply_with_markup(api, chat_id, &page.text, None, page.keyboard)
+                    .await?;
+            }
+            None => {
+                self.send_reply(api, chat_id, "当前群组还没有设置任何关键词回复。", None)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 处理翻页和排序按钮，回调数据格式为 `list:<种类>:<排序>:<页码>:<参数>`
+    pub(super) async fn handle_keyword_list_callback(
+        &self,
+        api: &Bot,
+        query: &CallbackQuery,
+        args: &str,
+    ) -> Result<()> {
+        let Some(MaybeInaccessibleMessage::Message(message)) = &query.message else {
+            self.answer_callback(api, query, Some("消息已过期")).await?;
+            return Ok(());
+        };
+
+        let mut parts = args.splitn(4, ':');
+        let kind = parts.next().and_then(|kind| kind.parse::<ListKind>().ok());
+        let sort = parts.next().and_then(|sort| sort.parse::<ListSort>().ok());
+        let page = parts.next().and_then(|page| page.parse::<usize>().ok());
+        let arg = parts.next().unwrap_or("");
+        let (Some(kind), Some(sort), Some(page)) = (kind, sort, page) else {
+            self.answer_callback(api, query, None).await?;
+            return Ok(());
+        };
+
+        // 私聊中的列表展示的是当前会话所管理的群组
+        let group_id = if self.is_private_chat(message) {
+            match self.session_group(query.from.id).await? {
+                Some(group_id) => group_id,
+                None => {
+                    self.answer_callback(api, query, Some("请先选择要管理的群组"))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        } else {
+            message.chat.id
+        };
+
+        let view_group_id = if kind == ListKind::Global {
+            GLOBAL_GROUP_ID
+        } else {
+            group_id
+        };
+        // 变更记录和回收站包含原回复和操作者，与对应的命令一样需要编辑权限
+        let allowed = if matches!(kind, ListKind::History | ListKind::Trash) {
+            self.user_role(api, group_id, query.from.id).await? >= Some(Role::Editor)
+        } else {
+            self.can_view(api, view_group_id, query.from.id).await?
+        };
+        if !allowed {
+            self.answer_callback(api, query, Some("你没有查看权限"))
+                .await?;
+            return Ok(());
+        }
+
+        let Some(page) = self
+            .render_list_page(group_id, kind, sort, page, arg)
+            .await?
+        else {
+            self.answer_callback(api, query, Some("列表已为空")).await?;
+            return Ok(());
+        };
+
+        // 在原消息上原地更新，消息仍按原计划自动删除
+        let params = EditMessageTextParams::builder()
+            .chat_id(message.chat.id)
+            .message_id(message.message_id)
+            .text(page.text)
+            .parse_mode(ParseMode::Html)
+            .maybe_reply_markup(page.keyboard)
+            .build();
+        if let Err(e) = api.edit_message_text(&params).await {
+            eprintln!("更新列表消息时出错: {}", e);
+        }
+        self.answer_callback(api, query, None).await?;
+
+        Ok(())
+    }
+
+    /// 渲染指定群组列表的某一页，列表为空时返回 None
+    pub(super) async fn render_list_page(
+        &self,
+        group_id: i64,
+        kind: ListKind,
+        sort: ListSort,
+        page: usize,
+        arg: &str,
+    ) -> Result<Option<ListPage>> {
+        let (title, lines) = match kind {
+            ListKind::All => (
+                "<b>当前群组的关键词列表:</b>".to_string(),
+                self.keyword_list_lines(group_id, sort).await?,
+            ),
+            ListKind::Global => (
+                "<b>全局关键词列表:</b>".to_string(),
+                self.keyword_list_lines(GLOBAL_GROUP_ID, sort).await?,
+            ),
+            ListKind::Search => (
+                format!("<b>“{}” 的搜索结果:</b>", self.escape_html(arg)),
+                self.search_list_lines(group_id, arg, sort).await?,
+            ),
+            ListKind::History if arg.is_empty() => (
+                "<b>当前群组的变更记录:</b>".to_string(),
+                self.history_list_lines(group_id, arg).await?,
+            ),
+            ListKind::History => (
+                format!("<b>“{}” 的变更记录:</b>", self.escape_html(arg)),
+                self.history_list_lines(group_id, arg).await?,
+            ),
+            ListKind::Trash => (
+                format!(
+                    "<b>回收站（保留 {} 天，使用 /restore 恢复）:</b>",
+                    self.config.trash_retention_days
+                ),
+                self.trash_list_lines(group_id).await?,
+            ),
+        };
+
+        if lines.is_empty() {
+            return Ok(None);
+        }
+
+        let pages = self.paginate_lines(&lines);
+        let page = page.min(pages.len() - 1);
+
+        let mut text = format!("{}\n\n", title);
+        text.push_str(&pages[page].join("\n"));
+
+        let keyboard = self.build_list_keyboard(kind, sort, page, pages.len(), arg);
+
+        Ok(Some(ListPage { text, keyboard }))
+    }
+
+    async fn keyword_list_lines(&self, chat_id: i64, sort: ListSort) -> Result<Vec<String>> {
+        let mut replies = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(chat_id))
+            .all(&self.db)
+            .await?;
+        if sort == ListSort::Name {
+            replies.sort_by(|a, b| a.keywords.cmp(&b.keywords));
+        }
+
+        let mut aliases = self.load_aliases(chat_id).await?;
+        let lines = replies
+            .iter()
+            .map(|reply| {
+                let mut line = format!("<code>{}</code>", self.escape_html(&reply.keywords));
+                // 别名与主关键词显示在同一行
+                for alias in aliases.remove(&reply.keywords).unwrap_or_default() {
+                    line.push_str(&format!(" | <code>{}</code>", self.escape_html(&alias)));
+                }
+                line
+            })
+            .collect();
+
+        Ok(lines)
+    }
+
+    /// 按条目数和文本长度将列表切分为若干页
+    fn paginate_lines<'a>(&self, lines: &'a [String]) -> Vec<Vec<&'a str>> {
+        let mut pages: Vec<Vec<&str>> = Vec::new();
+        let mut current: Vec<&str> = Vec::new();
+        let mut current_len = 0;
+
+        for line in lines {
+            let line_len = line.chars().count() + 1;
+            if !current.is_empty()
+                && (current.len() >= ITEMS_PER_PAGE || current_len + line_len > PAGE_TEXT_LIMIT)
+            {
+                pages.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            current.push(line);
+            current_len += line_len;
+        }
+        if !current.is_empty() {
+            pages.push(current);
+        }
+
+        pages
+    }
+
+    fn build_list_keyboard(
+        &self,
+        kind: ListKind,
+        sort: ListSort,
+        page: usize,
+        total_pages: usize,
+        arg: &str,
+    ) -> Option<InlineKeyboardMarkup> {
+        let callback = |sort: ListSort, page: usize| {
+            let data = format!(
+                "{}:{}:{}:{}:{}",
+                CALLBACK_PREFIX,
+                kind.as_str(),
+                sort.as_str(),
+                page,
+                arg
+            );
+            // Telegram 限制回调数据最多 64 字节
+            let mut end = data.len().min(64);
+            while !data.is_char_boundary(end) {
+                end -= 1;
+            }
+            data[..end].to_string()
+        };
+        let button = |text: String, data: String| {
+            InlineKeyboardButton::builder()
+                .text(text)
+                .callback_data(data)
+                .build()
+        };
+
+        let mut rows = Vec::new();
+        if total_pages > 1 {
+            let mut row = Vec::new();
+            if page > 0 {
+                row.push(button("« 上一页".to_string(), callback(sort, page - 1)));
+            }
+            row.push(button(
+                format!("{}/{}", page + 1, total_pages),
+                callback(sort, page),
+            ));
+            if page + 1 < total_pages {
+                row.push(button("下一页 »".to_string(), callback(sort, page + 1)));
+            }
+            rows.push(row);
+        }
+
+        // 变更记录和回收站固定按时间倒序，不提供排序按钮
+        if matches!(kind, ListKind::History | ListKind::Trash) {
+            return (!rows.is_empty()).then(|| {
+                InlineKeyboardMarkup::builder()
+                    .inline_keyboard(rows)
+                    .build()
+            });
+        }
+
+        let sort_row = [ListSort::Default, ListSort::Name]
+            .into_iter()
+            .map(|option| {
+                let text = if option == sort {
+                    format!("✓ {}", option.label())
+                } else {
+                    option.label().to_string()
+                };
+                button(text, callback(option, 0))
+            })
+            .collect();
+        rows.push(sort_row);
+
+        Some(
+            InlineKeyboardMarkup::builder()
+                .inline_keyboard(rows)
+                .build(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::test_handler;
+
+    #[test]
+    fn paginate_lines_splits_by_item_count() {
+        let lines: Vec<String> = (0..ITEMS_PER_PAGE * 2 + 1)
+            .map(|i| format!("关键词{}", i))
+            .collect();
+        let pages = test_handler().paginate_lines(&lines);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].len(), ITEMS_PER_PAGE);
+        assert_eq!(pages[2], vec![lines.last().unwrap().as_str()]);
+    }
+
+    #[test]
+    fn paginate_lines_splits_by_text_length() {
+        let long_line = "字".repeat(PAGE_TEXT_LIMIT / 2);
+        let lines = vec![long_line.clone(), long_line.clone(), long_line];
+        let pages = test_handler().paginate_lines(&lines);
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|page| page.len() == 1));
+    }
+
+    #[test]
+    fn paginate_lines_keeps_oversized_line() {
+        let lines = vec!["字".repeat(PAGE_TEXT_LIMIT * 2)];
+        assert_eq!(test_handler().paginate_lines(&lines).len(), 1);
+        assert!(test_handler().paginate_lines(&[]).is_empty());
+    }
+}
@@ -0,0 +1,534 @@
+use super::permission::{TargetUser, admin_has_right};
+use super::{GLOBAL_GROUP_ID, MessageHandler};
+use crate::config::AdminRight;
+use crate::entities::group_reply;
+use crate::entities::moderation_log;
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::{BanChatMemberParams, DeleteMessageParams, RestrictChatMemberParams};
+use frankenstein::types::{ChatMember, ChatPermissions, Message, User};
+use sea_orm::{ActiveModelTrait, Set};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+// Telegram 将短于 30 秒或长于 366 天的限制视为永久，只接受此范围内的时长
+const MIN_ACTION_DURATION_SECS: u32 = 30;
+const MAX_ACTION_DURATION_SECS: u32 = 366 * 24 * 60 * 60;
+
+// 同一群组中同一事项缺少权限的提示，在此间隔内只发送一次
+const RIGHT_NOTICE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 记录最近发送过的缺少权限提示，避免频繁触发时机器人在群里刷屏
+#[derive(Clone, Default)]
+pub(super) struct RightNotices {
+    sent_at: Arc<Mutex<HashMap<(i64, String), Instant>>>,
+}
+
+impl RightNotices {
+    /// 距离上次提示已超过间隔时记录本次提示并返回 true
+    pub(super) fn should_notify(&self, chat_id: i64, subject: &str) -> bool {
+        let mut sent_at = self.sent_at.lock().unwrap_or_else(PoisonError::into_inner);
+        sent_at.retain(|_, at| at.elapsed() < RIGHT_NOTICE_INTERVAL);
+
+        let key = (chat_id, subject.to_string());
+        if sent_at.contains_key(&key) {
+            return false;
+        }
+        sent_at.insert(key, Instant::now());
+        true
+    }
+}
+
+/// 关键词触发后对发送者执行的管理动作
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum KeywordAction {
+    /// 删除触发的消息
+    Delete,
+    /// 向发送者发出警告
+    Warn,
+    /// 禁言发送者
+    Mute,
+    /// 将发送者移出群组并禁止再次加入
+    Ban,
+}
+
+impl KeywordAction {
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            KeywordAction::Delete => "delete",
+            KeywordAction::Warn => "warn",
+            KeywordAction::Mute => "mute",
+            KeywordAction::Ban => "ban",
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            KeywordAction::Delete => "删除消息",
+            KeywordAction::Warn => "警告",
+            KeywordAction::Mute => "禁言",
+            KeywordAction::Ban => "封禁",
+        }
+    }
+
+    /// 机器人执行该动作所需的管理员权限
+    fn required_right(self) -> Option<AdminRight> {
+        match self {
+            KeywordAction::Delete => Some(AdminRight::DeleteMessages),
+            KeywordAction::Warn => None,
+            KeywordAction::Mute | KeywordAction::Ban => Some(AdminRight::RestrictMembers),
+        }
+    }
+
+    fn has_duration(self) -> bool {
+        matches!(self, KeywordAction::Mute | KeywordAction::Ban)
+    }
+}
+
+impl FromStr for KeywordAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delete" => Ok(KeywordAction::Delete),
+            "warn" => Ok(KeywordAction::Warn),
+            "mute" => Ok(KeywordAction::Mute),
+            "ban" => Ok(KeywordAction::Ban),
+            _ => Err(()),
+        }
+    }
+}
+
+impl MessageHandler {
+    /// 执行关键词配置的管理动作，返回触发消息是否已被删除
+    ///
+    /// 私聊、匿名管理员、频道身份发言以及群组管理员不受关键词动作影响，
+    /// 继承自订阅群组和全局的关键词只回复，不执行动作
+    pub(super) async fn run_keyword_action(
+        &self,
+        api: &Bot,
+        message: &Message,
+        reply: &group_reply::Model,
+    ) -> Result<bool> {
+        let Some(action) = reply
+            .action
+            .as_deref()
+            .and_then(|action| action.parse::<KeywordAction>().ok())
+        else {
+            return Ok(false);
+        };
+        if reply.group_id != message.chat.id
+            || self.is_private_chat(message)
+            || message.sender_chat.is_some()
+        {
+            return Ok(false);
+        }
+        let Some(user) = message.from.as_deref() else {
+            return Ok(false);
+        };
+        if self
+            .find_chat_admin(api, message.chat.id, user.id)
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        if let Some(right) = action.required_right()
+            && !self.bot_has_right(api, message.chat.id, right).await?
+        {
+            if !self
+                .right_notices
+                .should_notify(message.chat.id, &format!("keyword:{}", reply.keywords))
+            {
+                return Ok(false);
+            }
+            self.send_reply(
+                api.clone(),
+                message.chat.id,
+                &format!(
+                    "无法对关键词 <code>{}</code> 执行{}：机器人需要具备 <code>{}</code> 管理员权限",
+                    self.escape_html(&reply.keywords),
+                    action.label(),
+                    right.as_str()
+                ),
+                None,
+            )
+            .await?;
+            return Ok(false);
+        }
+
+        let reason = format!("触发关键词 {}", reply.keywords);
+        let user_mention = self.mention_user(user);
+
+        match action {
+            KeywordAction::Delete => {
+                let params = DeleteMessageParams::builder()
+                    .chat_id(message.chat.id)
+                    .message_id(message.message_id)
+                    .build();
+                if let Err(e) = api.delete_message(&params).await {
+                    eprintln!("删除触发消息时出错: {}", e);
+                    return Ok(false);
+                }
+                self.log_moderation(
+                    message.chat.id,
+                    user.id as i64,
+                    action.as_str(),
+                    Some(&reason),
+                    0,
+                )
+                .await?;
+                return Ok(true);
+            }
+            KeywordAction::Warn => {
+                let text = self
+                    .issue_warning(
+                        api,
+                        message.chat.id,
+                        TargetUser {
+                            user_id: user.id,
+                            username: user.username.clone(),
+                        },
+                        &user_mention,
+                        Some(reason),
+                        0,
+                    )
+                    .await?;
+                self.send_reply(api.clone(), message.chat.id, &text, None)
+                    .await?;
+            }
+            KeywordAction::Mute | KeywordAction::Ban => {
+                if !self
+                    .restrict_member(
+                        api,
+                        message.chat.id,
+                        user.id,
+                        action,
+                        reply.action_duration_secs,
+                    )
+                    .await?
+                {
+                    return Ok(false);
+                }
+                self.log_moderation(
+                    message.chat.id,
+                    user.id as i64,
+                    action.as_str(),
+                    Some(&reason),
+                    0,
+                )
+                .await?;
+                self.send_reply(
+                    api.clone(),
+                    message.chat.id,
+                    &format!(
+                        "{} 已被{}{}",
+                        user_mention,
+                        action.label(),
+                        self.format_action_duration(reply.action_duration_secs)
+                    ),
+                    None,
+                )
+                .await?;
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// 禁言或封禁群组成员，持续秒数为空时永久生效，调用失败时记录错误并返回 false
+    pub(super) async fn restrict_member(
+        &self,
+        api: &Bot,
+        chat_id: i64,
+        user_id: u64,
+        action: KeywordAction,
+        duration_secs: Option<i32>,
+    ) -> Result<bool> {
+        let until_date = duration_secs
+            .filter(|secs| *secs > 0)
+            .map(|secs| chrono::Utc::now().timestamp() as u64 + secs as u64);
+
+        let result = match action {
+            KeywordAction::Mute => {
+                let params = RestrictChatMemberParams::builder()
+                    .chat_id(chat_id)
+                    .user_id(user_id)
+                    .permissions(ChatPermissions::builder().can_send_messages(false).build())
+                    .maybe_until_date(until_date)
+                    .build();
+                api.restrict_chat_member(&params).await
+            }
+            KeywordAction::Ban => {
+                let params = BanChatMemberParams::builder()
+                    .chat_id(chat_id)
+                    .user_id(user_id)
+                    .maybe_until_date(until_date)
+                    .build();
+                api.ban_chat_member(&params).await
+            }
+            KeywordAction::Delete | KeywordAction::Warn => return Ok(false),
+        };
+        if let Err(e) = result {
+            eprintln!("{}用户 {} 时出错: {}", action.label(), user_id, e);
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// 写入一条管理操作记录
+    pub(super) async fn log_moderation(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        action: &str,
+        reason: Option<&str>,
+        actor_id: i64,
+    ) -> Result<()> {
+        println!(
+            "群组 {} 对用户 {} 执行 {}，操作者 {}",
+            group_id, user_id, action, actor_id
+        );
+        let log = moderation_log::ActiveModel {
+            group_id: Set(group_id),
+            user_id: Set(user_id),
+            action: Set(action.to_string()),
+            reason: Set(reason.map(str::to_string)),
+            actor_id: Set(actor_id),
+            created_at: Set(chrono::Utc::now().timestamp()),
+            ..Default::default()
+        };
+        log.insert(&self.db).await?;
+
+        Ok(())
+    }
+
+    /// 检查机器人在群组中是否为具备指定权限的管理员
+    pub(super) async fn bot_has_right(
+        &self,
+        api: &Bot,
+        chat_id: i64,
+        right: AdminRight,
+    ) -> Result<bool> {
+        let bot_id = self.bot_id(api).await?;
+        let has_right = match self.find_chat_admin(api, chat_id, bot_id).await? {
+            Some(ChatMember::Administrator(admin)) => admin_has_right(&admin, right),
+            _ => false,
+        };
+
+        Ok(has_right)
+    }
+
+    /// 生成可点击的用户提及，没有用户名的用户也能收到通知
+    pub(super) fn mention_user(&self, user: &User) -> String {
+        format!(
+            "<a href=\"tg://user?id={}\">{}</a>",
+            user.id,
+            self.escape_html(&user.first_name)
+        )
+    }
+
+    pub(super) async fn handle_action_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let usage = "用法: /action &lt;关键词&gt; delete|warn|mute [时长]|ban [时长]|none\n时长如 <code>30m</code>、<code>2h</code>、<code>7d</code>，省略则永久生效";
+        let content = self.get_content_after_command(message, command)?;
+        let args: Vec<&str> = content.split_whitespace().collect();
+        let Some((keywords, args)) = args.split_first() else {
+            self.send_reply(api, message.chat.id, usage, None).await?;
+            return Ok(());
+        };
+
+        let keywords = self.resolve_keyword(group_id, keywords).await?;
+        let Some(existing) = self.find_keyword_reply(group_id, &keywords).await? else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("未找到关键词 <code>{}</code>", self.escape_html(&keywords)),
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+        let keywords = self.escape_html(&keywords);
+
+        let (action, duration_secs) = match args {
+            [] => {
+                let text = match self.describe_keyword_action(&existing) {
+                    Some(action) => format!("关键词 <code>{}</code> 的动作: {}", keywords, action),
+                    None => format!(
+                        "关键词 <code>{}</code> 没有设置动作，触发时只回复",
+                        keywords
+                    ),
+                };
+                self.send_reply(api, message.chat.id, &text, None).await?;
+                return Ok(());
+            }
+            ["none"] => (None, None),
+            [action, rest @ ..] => {
+                let Ok(action) = action.parse::<KeywordAction>() else {
+                    self.send_reply(api, message.chat.id, usage, None).await?;
+                    return Ok(());
+                };
+                let duration_secs = match rest {
+                    [] => None,
+                    [duration] if action.has_duration() => {
+                        match self.parse_duration(duration).filter(|secs| {
+                            (MIN_ACTION_DURATION_SECS..=MAX_ACTION_DURATION_SECS).contains(secs)
+                        }) {
+                            Some(secs) => Some(secs as i32),
+                            None => {
+                                self.send_reply(
+                                    api,
+                                    message.chat.id,
+                                    "时长必须在 30 秒到 366 天之间",
+                                    None,
+                                )
+                                .await?;
+                                return Ok(());
+                            }
+                        }
+                    }
+                    _ => {
+                        self.send_reply(api, message.chat.id, usage, None).await?;
+                        return Ok(());
+                    }
+                };
+                (Some(action), duration_secs)
+            }
+        };
+
+        let mut active_model: group_reply::ActiveModel = existing.into();
+        active_model.action = Set(action.map(|action| action.as_str().to_string()));
+        active_model.action_duration_secs = Set(duration_secs);
+        let updated = active_model.update(&self.db).await?;
+
+        let mut message_text = match self.describe_keyword_action(&updated) {
+            Some(action) => format!(
+                "关键词 <code>{}</code> 的动作已设置为: {}",
+                keywords, action
+            ),
+            None => format!(
+                "关键词 <code>{}</code> 的动作已清除，触发时只回复",
+                keywords
+            ),
+        };
+        // 设置时提前提醒缺少的权限，避免触发时才发现无法执行
+        if group_id != GLOBAL_GROUP_ID
+            && let Some(right) = action.and_then(KeywordAction::required_right)
+            && !self.bot_has_right(&api, group_id, right).await?
+        {
+            message_text.push_str(&format!(
+                "\n注意：机器人当前不具备 <code>{}</code> 管理员权限，触发时将无法执行。授予权限后可发送 /reload 立即生效",
+                right.as_str()
+            ));
+        }
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 关键词动作的说明文字，没有设置动作时返回 None
+    pub(super) fn describe_keyword_action(&self, reply: &group_reply::Model) -> Option<String> {
+        let action = reply.action.as_deref()?.parse::<KeywordAction>().ok()?;
+        if action.has_duration() {
+            Some(format!(
+                "{}{}",
+                action.label(),
+                self.format_action_duration(reply.action_duration_secs)
+            ))
+        } else {
+            Some(action.label().to_string())
+        }
+    }
+
+    pub(super) fn format_action_duration(&self, secs: Option<i32>) -> String {
+        match secs.filter(|secs| *secs > 0) {
+            Some(secs) => format!(" {}", self.format_duration(secs as u32)),
+            None => "（永久）".to_string(),
+        }
+    }
+
+    /// 解析 `30s`、`10m`、`2h`、`7d` 形式的时长，纯数字按秒计算
+    pub(super) fn parse_duration(&self, text: &str) -> Option<u32> {
+        let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+            Some(index) => text.split_at(index),
+            None => (text, "s"),
+        };
+        let multiplier = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return None,
+        };
+
+        number.parse::<u32>().ok()?.checked_mul(multiplier)
+    }
+
+    /// 以能整除的最大单位显示时长
+    pub(super) fn format_duration(&self, secs: u32) -> String {
+        const UNITS: [(u32, &str); 3] = [(24 * 60 * 60, "天"), (60 * 60, "小时"), (60, "分钟")];
+        for (unit_secs, unit) in UNITS {
+            if secs >= unit_secs && secs.is_multiple_of(unit_secs) {
+                return format!("{} {}", secs / unit_secs, unit);
+            }
+        }
+        format!("{} 秒", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RightNotices;
+    use crate::handlers::test_handler;
+
+    #[test]
+    fn right_notices_only_notify_once_per_subject() {
+        let notices = RightNotices::default();
+        assert!(notices.should_notify(-100, "keyword:广告"));
+        assert!(!notices.should_notify(-100, "keyword:广告"));
+        assert!(notices.should_notify(-100, "keyword:群规"));
+        assert!(notices.should_notify(-200, "keyword:广告"));
+    }
+
+    #[test]
+    fn parse_duration_accepts_units_and_plain_seconds() {
+        let handler = test_handler();
+        assert_eq!(handler.parse_duration("45"), Some(45));
+        assert_eq!(handler.parse_duration("30s"), Some(30));
+        assert_eq!(handler.parse_duration("10m"), Some(600));
+        assert_eq!(handler.parse_duration("2h"), Some(7200));
+        assert_eq!(handler.parse_duration("7d"), Some(604_800));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_input() {
+        let handler = test_handler();
+        assert_eq!(handler.parse_duration(""), None);
+        assert_eq!(handler.parse_duration("m"), None);
+        assert_eq!(handler.parse_duration("10w"), None);
+        assert_eq!(handler.parse_duration("1h30m"), None);
+        assert_eq!(handler.parse_duration("99999999d"), None);
+    }
+
+    #[test]
+    fn format_duration_uses_largest_exact_unit() {
+        let handler = test_handler();
+        assert_eq!(handler.format_duration(86_400), "1 天");
+        assert_eq!(handler.format_duration(7200), "2 小时");
+        assert_eq!(handler.format_duration(90), "90 秒");
+        assert_eq!(handler.format_duration(5400), "90 分钟");
+        assert_eq!(handler.format_action_duration(None), "（永久）");
+    }
+}
@@ -0,0 +1,587 @@
+use super::{GLOBAL_GROUP_ID, MessageHandler};
+use crate::config::AdminRight;
+use crate::entities::group_role::{self, Entity as GroupRoleEntity};
+use crate::entities::ignored_user::{self, Entity as IgnoredUserEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::{ChatMember, ChatMemberAdministrator, Message, MessageEntityType, User};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use std::str::FromStr;
+
+/// 群组内的角色，按权限从低到高排列
+///
+/// 群组的创建者和管理员始终视为 owner，其余用户的角色由 `/grant` 授予
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Role {
+    /// 在限制查看的群组中仍可查看关键词
+    Viewer,
+    /// 可以修改关键词
+    Editor,
+    /// 可以管理角色和群组设置
+    Owner,
+}
+
+impl Role {
+    fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Owner => "owner",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Role::Viewer => "查看者",
+            Role::Editor => "编辑者",
+            Role::Owner => "所有者",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "owner" => Ok(Role::Owner),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 命令所需的权限
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Permission {
+    /// 查看关键词，群组开启 `/restrict_list` 后需要任意角色
+    View,
+    /// 修改关键词，需要 editor 及以上角色
+    Edit,
+    /// 管理角色和群组设置，需要 owner 角色
+    Manage,
+    /// 警告、禁言、验证等管理群成员的操作，只有真正的群组管理员可以执行，
+    /// 通过 `/grant` 获得的 owner 角色不够
+    Moderate,
+}
+
+impl Permission {
+    /// 命令与所需权限的对应关系，返回 None 的命令不做权限检查
+    pub(super) fn for_command(command: &str) -> Option<Self> {
+        match command {
+            "/all" | "/get" | "/search" | "/gall" => Some(Permission::View),
+            "/add" | "/del" | "/alias" | "/unalias" | "/edit" | "/append" | "/rename"
+            | "/exclude" | "/require" | "/test" | "/export" | "/import" | "/copyfrom"
+            | "/subscribe" | "/unsubscribe" | "/gadd" | "/gdel" | "/history" | "/undo"
+            | "/trash" | "/restore" | "/stats" | "/cooldown" => Some(Permission::Edit),
+            "/global" | "/grant" | "/revoke" | "/roles" | "/restrict_list" | "/reload" => {
+                Some(Permission::Manage)
+            }
+            "/del_all" | "/ratelimit" | "/ignore" | "/unignore" | "/filter" | "/action"
+            | "/warn" | "/warns" | "/resetwarns" | "/warnconfig" | "/modlog" | "/setwelcome"
+            | "/setgoodbye" | "/welcome" | "/goodbye" | "/captcha" | "/schedule" | "/schedules"
+            | "/unschedule" => Some(Permission::Moderate),
+            _ => None,
+        }
+    }
+}
+
+/// `/grant`、`/revoke` 和 `/ignore` 等命令的目标用户
+pub(super) struct TargetUser {
+    pub user_id: u64,
+    pub username: Option<String>,
+}
+
+impl MessageHandler {
+    /// 查询用户在群组中的有效角色，全局关键词只有机器人所有者拥有角色
+    pub(super) async fn user_role(
+        &self,
+        api: &Bot,
+        group_id: i64,
+        user_id: u64,
+    ) -> Result<Option<Role>> {
+        if group_id == GLOBAL_GROUP_ID {
+            let is_owner = self.config.owner_id == Some(user_id);
+            return Ok(is_owner.then_some(Role::Owner));
+        }
+
+        let admin_role = self.admin_role(api, group_id, user_id).await?;
+        if admin_role == Some(Role::Owner) {
+            return Ok(admin_role);
+        }
+
+        let granted_role = GroupRoleEntity::find_by_id((group_id, user_id as i64))
+            .one(&self.db)
+            .await?
+            .and_then(|role| role.role.parse().ok());
+
+        Ok(admin_role.max(granted_role))
+    }
+
+    /// 群组管理员对应的角色
+    ///
+    /// 创建者以及具备 `ADMIN_REQUIRED_RIGHT` 所配置权限的管理员为 owner，
+    /// 缺少该权限的管理员只能查看，非管理员返回 None
+    async fn admin_role(&self, api: &Bot, chat_id: i64, user_id: u64) -> Result<Option<Role>> {
+        let member = self.find_chat_admin(api, chat_id, user_id).await?;
+        let role = match &member {
+            Some(ChatMember::Creator(_)) => Some(Role::Owner),
+            Some(ChatMember::Administrator(admin)) => {
+                let has_right = self
+                    .config
+                    .admin_required_right
+                    .is_none_or(|right| admin_has_right(admin, right));
+                Some(if has_right { Role::Owner } else { Role::Viewer })
+            }
+            _ => None,
+        };
+
+        Ok(role)
+    }
+
+    /// 检查用户能否查看群组的关键词
+    pub(super) async fn can_view(&self, api: &Bot, group_id: i64, user_id: u64) -> Result<bool> {
+        if group_id != GLOBAL_GROUP_ID && !self.load_group_setting(group_id).await?.list_restricted
+        {
+            return Ok(true);
+        }
+
+        Ok(self.user_role(api, group_id, user_id).await?.is_some())
+    }
+
+    /// 检查用户在群组中是否具备指定权限
+    pub(super) async fn has_permission(
+        &self,
+        api: &Bot,
+        group_id: i64,
+        user_id: u64,
+        permission: Permission,
+    ) -> Result<bool> {
+        let allowed = match permission {
+            Permission::View => self.can_view(api, group_id, user_id).await?,
+            Permission::Edit => self.user_role(api, group_id, user_id).await? >= Some(Role::Editor),
+            Permission::Manage => {
+                self.user_role(api, group_id, user_id).await? >= Some(Role::Owner)
+            }
+            Permission::Moderate => {
+                group_id != GLOBAL_GROUP_ID
+                    && self.admin_role(api, group_id, user_id).await? == Some(Role::Owner)
+            }
+        };
+
+        Ok(allowed)
+    }
+
+    /// 检查消息的发送者在群组中是否具备指定权限，不发送任何提示
+    pub(super) async fn sender_has_permission(
+        &self,
+        api: &Bot,
+        message: &Message,
+        group_id: i64,
+        permission: Permission,
+    ) -> Result<bool> {
+        // 匿名管理员以群组身份发言，from 为 GroupAnonymousBot，只能通过 sender_chat 识别
+        let anonymous_admin = message
+            .sender_chat
+            .as_ref()
+            .is_some_and(|chat| chat.id == message.chat.id && chat.id == group_id);
+
+        if anonymous_admin {
+            return Ok(true);
+        }
+        match &message.from {
+            Some(from) => {
+                self.has_permission(api, group_id, from.id, permission)
+                    .await
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// 统一的权限检查，不满足时向发送者说明原因
+    pub(super) async fn check_permission(
+        &self,
+        api: &Bot,
+        message: &Message,
+        group_id: i64,
+        permission: Permission,
+    ) -> Result<bool> {
+        let allowed = self
+            .sender_has_permission(api, message, group_id, permission)
+            .await?;
+
+        if !allowed {
+            let text = if group_id == GLOBAL_GROUP_ID {
+                "只有机器人所有者才能管理全局关键词".to_string()
+            } else {
+                match permission {
+                    Permission::View => "本群的关键词仅限授权用户查看".to_string(),
+                    Permission::Edit => self.missing_right_text("只有管理员或编辑者才能使用此命令"),
+                    Permission::Manage => self.missing_right_text("只有管理员才能使用此命令"),
+                    Permission::Moderate => {
+                        self.missing_right_text("只有群组管理员才能使用此命令，授予的角色不适用")
+                    }
+                }
+            };
+            self.send_reply(api.clone(), message.chat.id, &text, None)
+                .await?;
+        }
+
+        Ok(allowed)
+    }
+
+    /// 配置了所需权限时，在拒绝提示中说明管理员还需要具备的权限
+    fn missing_right_text(&self, text: &str) -> String {
+        match self.config.admin_required_right {
+            Some(right) => format!(
+                "{}，管理员还需要具备 <code>{}</code> 权限",
+                text,
+                right.as_str()
+            ),
+            None => text.to_string(),
+        }
+    }
+
+    pub(super) async fn handle_grant_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let content = content.trim();
+
+        // 角色总是最后一个参数，之前的部分是目标用户
+        let (target, role) = match content.rsplit_once(char::is_whitespace) {
+            Some((target, role)) => (target.trim(), role),
+            None => ("", content),
+        };
+        let Ok(role) = role.parse::<Role>() else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "用法: /grant &lt;@用户名|用户ID&gt; owner|editor|viewer，也可以回复用户的消息使用 /grant &lt;角色&gt;",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, target)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let existing = GroupRoleEntity::find_by_id((group_id, target.user_id as i64))
+            .one(&self.db)
+            .await?;
+        if let Some(existing) = existing {
+            let mut active_model: group_role::ActiveModel = existing.into();
+            active_model.role = Set(role.as_str().to_string());
+            if target.username.is_some() {
+                active_model.username = Set(target.username.clone());
+            }
+            active_model.update(&self.db).await?;
+        } else {
+            let group_role = group_role::ActiveModel {
+                group_id: Set(group_id),
+                user_id: Set(target.user_id as i64),
+                role: Set(role.as_str().to_string()),
+                username: Set(target.username.clone()),
+            };
+            group_role.insert(&self.db).await?;
+        }
+
+        self.send_reply(
+            api,
+            message.chat.id,
+            &format!(
+                "已将 {} 设为{}",
+                self.format_user(target.user_id as i64, target.username.as_deref()),
+                role.label()
+            ),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_revoke_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, content.trim())
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let result = GroupRoleEntity::delete_by_id((group_id, target.user_id as i64))
+            .exec(&self.db)
+            .await?;
+        let user = self.format_user(target.user_id as i64, target.username.as_deref());
+        let message_text = if result.rows_affected > 0 {
+            format!("已撤销 {} 的角色", user)
+        } else {
+            format!("{} 没有被授予角色", user)
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 列出群组中通过 `/grant` 授予的角色，群组管理员不在此列
+    pub(super) async fn handle_roles_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let mut roles = GroupRoleEntity::find()
+            .filter(group_role::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?;
+        if roles.is_empty() {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "当前群组没有授予任何角色，群组管理员默认拥有全部权限",
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        roles.sort_by_key(|role| std::cmp::Reverse(role.role.parse::<Role>().ok()));
+        let mut text = "<b>当前群组的角色:</b>\n".to_string();
+        for role in roles {
+            let label = role.role.parse::<Role>().map(Role::label).unwrap_or("未知");
+            text.push_str(&format!(
+                "{} - {}\n",
+                self.format_user(role.user_id, role.username.as_deref()),
+                label
+            ));
+        }
+        text.push_str("\n群组管理员默认拥有全部权限");
+
+        self.send_reply(api, message.chat.id, &text, None).await?;
+
+        Ok(())
+    }
+
+    /// 开启后只有拥有角色的用户才能查看本群的关键词
+    pub(super) async fn handle_restrict_list_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let restricted = match content.trim() {
+            "on" => true,
+            "off" => false,
+            "" => {
+                let setting = self.load_group_setting(group_id).await?;
+                let status = if setting.list_restricted {
+                    "仅拥有角色的用户可以查看"
+                } else {
+                    "所有人都可以查看"
+                };
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!("当前群组的关键词{}\n用法: /restrict_list on|off", status),
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+            _ => {
+                self.send_reply(api, message.chat.id, "用法: /restrict_list on|off", None)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        self.update_group_setting(group_id, |setting| {
+            setting.list_restricted = Set(restricted);
+        })
+        .await?;
+
+        let message_text = if restricted {
+            "已限制查看，只有管理员和被授予角色的用户可以使用 /all、/get 和 /search"
+        } else {
+            "已取消限制，所有人都可以查看本群的关键词"
+        };
+        self.send_reply(api, message.chat.id, message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 被回复消息的发送者，可以作为命令的目标用户
+    ///
+    /// 论坛话题中的消息都回复话题的创建消息，匿名发言的 from 为 GroupAnonymousBot，
+    /// 这些回复以及回复机器人自己的消息都不算指定了目标
+    pub(super) async fn replied_user<'a>(
+        &self,
+        api: &Bot,
+        message: &'a Message,
+    ) -> Result<Option<&'a User>> {
+        let Some(user) = message
+            .reply_to_message
+            .as_deref()
+            .filter(|reply| reply.sender_chat.is_none() && reply.forum_topic_created.is_none())
+            .and_then(|reply| reply.from.as_deref())
+        else {
+            return Ok(None);
+        };
+        if user.id == self.bot_id(api).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(user))
+    }
+
+    /// 依次从文字提及、用户 ID、已记录的用户名和被回复的消息中确定目标用户
+    ///
+    /// 明确写出的目标优先，只有省略参数时才使用被回复消息的发送者
+    pub(super) async fn resolve_target_user(
+        &self,
+        api: &Bot,
+        message: &Message,
+        group_id: i64,
+        target: &str,
+    ) -> Result<Option<TargetUser>> {
+        // 没有用户名的用户在输入框中选择后会生成文字提及
+        let mention = message.entities.iter().flatten().find_map(|entity| {
+            (entity.type_field == MessageEntityType::TextMention)
+                .then_some(entity.user.as_ref())
+                .flatten()
+        });
+        if let Some(user) = mention {
+            return Ok(Some(TargetUser {
+                user_id: user.id,
+                username: user.username.clone(),
+            }));
+        }
+
+        if let Ok(user_id) = target.parse::<u64>() {
+            return Ok(Some(TargetUser {
+                user_id,
+                username: None,
+            }));
+        }
+
+        if let Some(username) = target.strip_prefix('@') {
+            // Bot API 无法按用户名查询用户，只能匹配角色和忽略列表中已经记录过的用户名
+            let roles = GroupRoleEntity::find()
+                .filter(group_role::Column::GroupId.eq(group_id))
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|role| (role.user_id, role.username));
+            let ignored = IgnoredUserEntity::find()
+                .filter(ignored_user::Column::GroupId.eq(group_id))
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|ignored| (ignored.user_id, ignored.username));
+            let known = roles.chain(ignored).find(|(_, known)| {
+                known
+                    .as_deref()
+                    .is_some_and(|known| known.eq_ignore_ascii_case(username))
+            });
+            if let Some((user_id, username)) = known {
+                return Ok(Some(TargetUser {
+                    user_id: user_id as u64,
+                    username,
+                }));
+            }
+
+            self.send_reply(
+                api.clone(),
+                message.chat.id,
+                &format!(
+                    "找不到用户 @{}，请回复该用户的消息使用此命令，或改用用户 ID",
+                    self.escape_html(username)
+                ),
+                None,
+            )
+            .await?;
+            return Ok(None);
+        }
+
+        if target.is_empty()
+            && let Some(user) = self.replied_user(api, message).await?
+        {
+            return Ok(Some(TargetUser {
+                user_id: user.id,
+                username: user.username.clone(),
+            }));
+        }
+
+        self.send_reply(
+            api.clone(),
+            message.chat.id,
+            "请指定用户：回复该用户的消息，或提供 @用户名 或用户 ID",
+            None,
+        )
+        .await?;
+
+        Ok(None)
+    }
+
+    pub(super) fn format_user(&self, user_id: i64, username: Option<&str>) -> String {
+        match username {
+            Some(username) => format!("@{} (<code>{}</code>)", self.escape_html(username), user_id),
+            None => format!("<code>{}</code>", user_id),
+        }
+    }
+}
+
+pub(super) fn admin_has_right(admin: &ChatMemberAdministrator, right: AdminRight) -> bool {
+    match right {
+        AdminRight::ManageChat => admin.can_manage_chat,
+        AdminRight::ChangeInfo => admin.can_change_info,
+        AdminRight::DeleteMessages => admin.can_delete_messages,
+        AdminRight::RestrictMembers => admin.can_restrict_members,
+        AdminRight::InviteUsers => admin.can_invite_users,
+        AdminRight::PinMessages => admin.can_pin_messages.unwrap_or(false),
+        AdminRight::PromoteMembers => admin.can_promote_members,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Permission;
+
+    #[test]
+    fn moderation_commands_need_chat_admins() {
+        for command in [
+            "/warn",
+            "/action",
+            "/captcha",
+            "/schedule",
+            "/del_all",
+            "/ignore",
+        ] {
+            assert!(Permission::for_command(command) == Some(Permission::Moderate));
+        }
+        assert!(Permission::for_command("/grant") == Some(Permission::Manage));
+        assert!(Permission::for_command("/add") == Some(Permission::Edit));
+        assert!(Permission::for_command("/help").is_none());
+    }
+}
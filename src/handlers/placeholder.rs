@@ -0,0 +1,148 @@
+use super::MessageHandler;
+use frankenstein::types::{Message, User};
+
+/// 回复模板中支持的占位符，替换为发送者或成员的信息
+pub(super) const PLACEHOLDER_HELP: &str =
+    "{name} 名字，{mention} 可点击的提及，{username} 用户名，{id} 用户 ID，{group} 群组名称";
+
+impl MessageHandler {
+    /// 替换模板中的占位符，替换的内容会转义 HTML，模板本身的 HTML 保持不变
+    ///
+    /// 未指定用户时使用消息的发送者，以频道或匿名管理员身份发送的消息使用对应聊天的信息
+    pub(super) fn render_placeholders(
+        &self,
+        template: &str,
+        message: &Message,
+        user: Option<&User>,
+    ) -> String {
+        if !template.contains('{') {
+            return template.to_string();
+        }
+
+        let (name, mention, username, id) = match (user, &message.sender_chat) {
+            (Some(user), _) => self.user_placeholders(user),
+            (None, Some(chat)) => {
+                let name = self.escape_html(chat.title.as_deref().unwrap_or_default());
+                let username = chat
+                    .username
+                    .as_ref()
+                    .map_or_else(|| name.clone(), |username| format!("@{}", username));
+                (name.clone(), name, username, chat.id.to_string())
+            }
+            (None, None) => match message.from.as_deref() {
+                Some(user) => self.user_placeholders(user),
+                None => Default::default(),
+            },
+        };
+        let group = self.escape_html(message.chat.title.as_deref().unwrap_or_default());
+
+        fill_placeholders(
+            template,
+            &[
+                ("{name}", &name),
+                ("{mention}", &mention),
+                ("{username}", &username),
+                ("{id}", &id),
+                ("{group}", &group),
+            ],
+        )
+    }
+
+    /// 以指定用户替换占位符，用于没有对应消息的内联查询，群组名称由调用方提供
+    pub(super) fn render_user_placeholders(
+        &self,
+        template: &str,
+        user: &User,
+        group: &str,
+    ) -> String {
+        if !template.contains('{') {
+            return template.to_string();
+        }
+
+        let (name, mention, username, id) = self.user_placeholders(user);
+        let group = self.escape_html(group);
+        fill_placeholders(
+            template,
+            &[
+                ("{name}", &name),
+                ("{mention}", &mention),
+                ("{username}", &username),
+                ("{id}", &id),
+                ("{group}", &group),
+            ],
+        )
+    }
+
+    fn user_placeholders(&self, user: &User) -> (String, String, String, String) {
+        let full_name = match &user.last_name {
+            Some(last_name) => format!("{} {}", user.first_name, last_name),
+            None => user.first_name.clone(),
+        };
+        let name = self.escape_html(&full_name);
+        let mention = format!("<a href=\"tg://user?id={}\">{}</a>", user.id, name);
+        let username = user
+            .username
+            .as_ref()
+            .map_or_else(|| name.clone(), |username| format!("@{}", username));
+        (name, mention, username, user.id.to_string())
+    }
+}
+
+/// 单次扫描模板替换占位符，替换进去的内容不会再被当作占位符展开
+fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                rendered.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fill_placeholders;
+    use crate::handlers::test_handler;
+    use frankenstein::types::User;
+
+    #[test]
+    fn fill_placeholders_replaces_known_names_once() {
+        let values = [("{name}", "{group}"), ("{group}", "测试群")];
+        assert_eq!(
+            fill_placeholders("{name} 欢迎来到 {group} {unknown} {", &values),
+            "{group} 欢迎来到 测试群 {unknown} {"
+        );
+    }
+
+    #[test]
+    fn user_values_are_escaped_and_not_expanded_again() {
+        let handler = test_handler();
+        let user = User::builder()
+            .id(42)
+            .is_bot(false)
+            .first_name("{mention}<b>")
+            .build();
+        assert_eq!(
+            handler.render_user_placeholders("{name} ({id}) @ {group}", &user, "A&B"),
+            "{mention}&lt;b&gt; (42) @ A&amp;B"
+        );
+        assert_eq!(
+            handler.render_user_placeholders("{mention}", &user, ""),
+            "<a href=\"tg://user?id=42\">{mention}&lt;b&gt;</a>"
+        );
+    }
+}
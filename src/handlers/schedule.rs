@@ -0,0 +1,496 @@
+use super::MessageHandler;
+use crate::entities::scheduled_message::{self, Entity as ScheduledMessageEntity};
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::ParseMode;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::{PinChatMessageParams, SendMessageParams};
+use frankenstein::types::Message;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use std::str::FromStr;
+
+// 每个群组最多保留的定时消息数量
+const MAX_SCHEDULES_PER_GROUP: u64 = 20;
+
+// 固定间隔的范围，过短的间隔容易刷屏
+const MIN_SCHEDULE_INTERVAL_SECS: u32 = 60;
+const MAX_SCHEDULE_INTERVAL_SECS: u32 = 366 * 24 * 60 * 60;
+
+const SCHEDULE_USAGE: &str = "用法: /schedule [once] &lt;间隔|\"cron 表达式\"&gt; [--pin] [--delete] &lt;内容&gt;\n间隔如 <code>30m</code>、<code>2h</code>、<code>1d</code>；cron 表达式为 5 段，如 <code>\"0 9 * * Mon-Fri\"</code> 表示工作日 9 点，星期可写英文缩写或 0-7（0 和 7 为周日）\nonce 表示只发送一次，--pin 发送后置顶，--delete 发送后自动删除";
+
+/// 定时消息的发送时间规则
+enum ScheduleSpec {
+    /// 5 段的 cron 表达式，按机器人所在时区计算
+    Cron(String),
+    /// 固定的间隔秒数
+    Interval(u32),
+}
+
+impl ScheduleSpec {
+    fn from_model(job: &scheduled_message::Model) -> Option<Self> {
+        match (&job.cron_expr, job.interval_secs) {
+            (Some(expr), _) => Some(ScheduleSpec::Cron(expr.clone())),
+            (None, Some(secs)) if secs > 0 => Some(ScheduleSpec::Interval(secs as u32)),
+            _ => None,
+        }
+    }
+
+    /// 计算下一次发送的时间，previous 为上一次计划发送的时间
+    fn next_run(&self, previous: i64, now: i64) -> Option<i64> {
+        match self {
+            ScheduleSpec::Cron(expr) => {
+                let schedule = parse_cron(expr)?;
+                let now = chrono::DateTime::from_timestamp(now, 0)?.with_timezone(&chrono::Local);
+                schedule.after(&now).next().map(|time| time.timestamp())
+            }
+            ScheduleSpec::Interval(secs) => {
+                // 机器人停止期间错过的发送不再补发
+                let next = previous + i64::from(*secs);
+                Some(if next > now {
+                    next
+                } else {
+                    now + i64::from(*secs)
+                })
+            }
+        }
+    }
+}
+
+/// cron 库要求包含秒，5 段表达式在前面补上 0 秒，星期字段转换为 cron 库的编号
+fn parse_cron(expr: &str) -> Option<cron::Schedule> {
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let [minute, hour, day, month, weekday] = fields.as_slice() else {
+        return None;
+    };
+    let weekday = translate_weekdays(weekday)?;
+    cron::Schedule::from_str(&format!(
+        "0 {} {} {} {} {}",
+        minute, hour, day, month, weekday
+    ))
+    .ok()
+}
+
+/// 标准 cron 的星期数字中 0 和 7 为周日、1 为周一，cron 库则是 1 为周日、7 为周六
+///
+/// 英文缩写保持不变，超出 0-7 的数字返回 None
+fn translate_weekdays(field: &str) -> Option<String> {
+    let mut items = Vec::new();
+    for item in field.split(',') {
+        let (base, step) = match item.split_once('/') {
+            Some((base, step)) => (base, Some(step)),
+            None => (item, None),
+        };
+        let translated = match base.split_once('-') {
+            Some((start, "7")) => match start {
+                "0" => "1-7".to_string(),
+                "7" => "1".to_string(),
+                // 以周日结尾的范围会越过 cron 库的周六，拆成两段，带步长时无法拆分
+                _ if step.is_some() => return None,
+                _ => {
+                    items.push(format!("{}-7", translate_weekday(start)?));
+                    items.push("1".to_string());
+                    continue;
+                }
+            },
+            Some((start, end)) => {
+                format!("{}-{}", translate_weekday(start)?, translate_weekday(end)?)
+            }
+            None => translate_weekday(base)?,
+        };
+        items.push(match step {
+            Some(step) => format!("{}/{}", translated, step),
+            None => translated,
+        });
+    }
+    Some(items.join(","))
+}
+
+fn translate_weekday(value: &str) -> Option<String> {
+    match value.parse::<u8>() {
+        Ok(day @ 0..=7) => Some((day % 7 + 1).to_string()),
+        Ok(_) => None,
+        Err(_) => Some(value.to_string()),
+    }
+}
+
+impl MessageHandler {
+    /// 发送到期的定时消息并安排下一次发送，返回发送的数量
+    pub async fn run_scheduled_messages(&self, api: &Bot) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let due = ScheduledMessageEntity::find()
+            .filter(scheduled_message::Column::NextRunAt.lte(now))
+            .all(&self.db)
+            .await?;
+
+        let mut sent = 0;
+        for job in &due {
+            // 先写入下次发送时间再发送，写入失败时跳过本次，避免每次检查都重复发送
+            if let Err(e) = self.advance_scheduled_message(job, now).await {
+                eprintln!("更新定时消息 #{} 时出错: {}", job.id, e);
+                continue;
+            }
+            self.send_scheduled_message(api, job).await;
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+
+    /// 将定时消息推进到下次发送时间，一次性或没有下次发送时间的消息直接删除
+    async fn advance_scheduled_message(
+        &self,
+        job: &scheduled_message::Model,
+        now: i64,
+    ) -> Result<()> {
+        let next_run = if job.one_shot {
+            None
+        } else {
+            ScheduleSpec::from_model(job).and_then(|spec| spec.next_run(job.next_run_at, now))
+        };
+        match next_run {
+            Some(next_run) => {
+                let mut active_model: scheduled_message::ActiveModel = job.clone().into();
+                active_model.next_run_at = Set(next_run);
+                active_model.update(&self.db).await?;
+            }
+            None => {
+                ScheduledMessageEntity::delete_by_id(job.id)
+                    .exec(&self.db)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_scheduled_message(&self, api: &Bot, job: &scheduled_message::Model) {
+        let params = SendMessageParams::builder()
+            .chat_id(job.group_id)
+            .text(&job.message)
+            .parse_mode(ParseMode::Html)
+            .build();
+        let message_id = match api.send_message(&params).await {
+            Ok(response) => response.result.message_id,
+            Err(e) => {
+                eprintln!("发送定时消息 #{} 时出错: {}", job.id, e);
+                return;
+            }
+        };
+
+        if job.pin {
+            let params = PinChatMessageParams::builder()
+                .chat_id(job.group_id)
+                .message_id(message_id)
+                .build();
+            if let Err(e) = api.pin_chat_message(&params).await {
+                eprintln!("置顶定时消息 #{} 时出错: {}", job.id, e);
+            }
+        }
+        if job.auto_delete {
+            self.delete_later(api.clone(), job.group_id, message_id, None);
+        }
+    }
+
+    pub(super) async fn handle_schedule_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let mut rest = content.trim_start();
+
+        let one_shot = match rest.split_once(char::is_whitespace) {
+            Some(("once", remaining)) => {
+                rest = remaining.trim_start();
+                true
+            }
+            _ => false,
+        };
+
+        // cron 表达式包含空格，需要用引号包裹，部分客户端会把引号自动替换为中文引号
+        let spec = if let Some(quoted) = rest.strip_prefix(['"', '“']) {
+            let Some((expr, remaining)) = quoted.split_once(['"', '”']) else {
+                self.send_reply(api, message.chat.id, SCHEDULE_USAGE, None)
+                    .await?;
+                return Ok(());
+            };
+            rest = remaining.trim_start();
+            let expr = expr.split_whitespace().collect::<Vec<_>>().join(" ");
+            if parse_cron(&expr).is_none() {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!(
+                        "无法解析 cron 表达式 <code>{}</code>，需要 5 段：分 时 日 月 星期",
+                        self.escape_html(&expr)
+                    ),
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+            ScheduleSpec::Cron(expr)
+        } else {
+            let Some((interval, remaining)) = rest.split_once(char::is_whitespace) else {
+                self.send_reply(api, message.chat.id, SCHEDULE_USAGE, None)
+                    .await?;
+                return Ok(());
+            };
+            let Some(secs) = self.parse_duration(interval) else {
+                self.send_reply(api, message.chat.id, SCHEDULE_USAGE, None)
+                    .await?;
+                return Ok(());
+            };
+            if !(MIN_SCHEDULE_INTERVAL_SECS..=MAX_SCHEDULE_INTERVAL_SECS).contains(&secs) {
+                self.send_reply(api, message.chat.id, "间隔必须在 1 分钟到 366 天之间", None)
+                    .await?;
+                return Ok(());
+            }
+            rest = remaining.trim_start();
+            ScheduleSpec::Interval(secs)
+        };
+
+        let mut pin = false;
+        let mut auto_delete = false;
+        while let Some((option, remaining)) = rest.split_once(char::is_whitespace) {
+            // 部分客户端会把 -- 自动替换为破折号
+            let Some(option) = option
+                .strip_prefix("--")
+                .or_else(|| option.strip_prefix('—'))
+            else {
+                break;
+            };
+            match option {
+                "pin" => pin = true,
+                "delete" => auto_delete = true,
+                _ => break,
+            }
+            rest = remaining.trim_start();
+        }
+        if rest.is_empty() {
+            self.send_reply(api, message.chat.id, SCHEDULE_USAGE, None)
+                .await?;
+            return Ok(());
+        }
+
+        let count = ScheduledMessageEntity::find()
+            .filter(scheduled_message::Column::GroupId.eq(group_id))
+            .count(&self.db)
+            .await?;
+        if count >= MAX_SCHEDULES_PER_GROUP {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!(
+                    "每个群组最多 {} 条定时消息，请先用 /unschedule 删除不需要的",
+                    MAX_SCHEDULES_PER_GROUP
+                ),
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let Some(next_run_at) = spec.next_run(now, now) else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "该 cron 表达式不会再触发，请检查日期",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        // 与关键词回复相同，保留 HTML 并将 code 实体转换为 <code> 标签
+        let text = self.process_reply_with_entities(rest, message).await?;
+        let (cron_expr, interval_secs) = match &spec {
+            ScheduleSpec::Cron(expr) => (Some(expr.clone()), None),
+            ScheduleSpec::Interval(secs) => (None, Some(*secs as i32)),
+        };
+        let job = scheduled_message::ActiveModel {
+            group_id: Set(group_id),
+            cron_expr: Set(cron_expr),
+            interval_secs: Set(interval_secs),
+            one_shot: Set(one_shot),
+            message: Set(text),
+            pin: Set(pin),
+            auto_delete: Set(auto_delete),
+            next_run_at: Set(next_run_at),
+            created_by: Set(self.actor_id(message)),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        self.send_reply(
+            api,
+            message.chat.id,
+            &format!(
+                "已创建定时消息 #{}，{}\n下次发送: {}",
+                job.id,
+                self.describe_schedule(&job),
+                self.format_timestamp(job.next_run_at)
+            ),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_schedules_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let jobs = ScheduledMessageEntity::find()
+            .filter(scheduled_message::Column::GroupId.eq(group_id))
+            .order_by_asc(scheduled_message::Column::Id)
+            .all(&self.db)
+            .await?;
+        if jobs.is_empty() {
+            let text = format!("本群没有定时消息\n{}", SCHEDULE_USAGE);
+            self.send_reply(api, message.chat.id, &text, None).await?;
+            return Ok(());
+        }
+
+        let mut text = "<b>定时消息:</b>\n".to_string();
+        for job in &jobs {
+            text.push_str(&format!(
+                "<b>#{}</b> {}，下次发送: {}\n    {}\n",
+                job.id,
+                self.describe_schedule(job),
+                self.format_timestamp(job.next_run_at),
+                self.reply_preview(&job.message)
+            ));
+        }
+        text.push_str("\n使用 /unschedule &lt;ID&gt; 删除");
+        self.send_reply(api, message.chat.id, &text, None).await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_unschedule_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let Ok(id) = content.trim().trim_start_matches('#').parse::<i32>() else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "用法: /unschedule &lt;ID&gt;，ID 可通过 /schedules 查看",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        // 只能删除本群的定时消息
+        let result = ScheduledMessageEntity::delete_many()
+            .filter(scheduled_message::Column::Id.eq(id))
+            .filter(scheduled_message::Column::GroupId.eq(group_id))
+            .exec(&self.db)
+            .await?;
+        let message_text = if result.rows_affected > 0 {
+            format!("已删除定时消息 #{}", id)
+        } else {
+            format!("本群没有 ID 为 {} 的定时消息", id)
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    fn describe_schedule(&self, job: &scheduled_message::Model) -> String {
+        let mut description = match ScheduleSpec::from_model(job) {
+            Some(ScheduleSpec::Cron(expr)) if job.one_shot => {
+                format!("在 <code>{}</code> 的下一个时间发送一次", expr)
+            }
+            Some(ScheduleSpec::Cron(expr)) => format!("按 <code>{}</code> 发送", expr),
+            Some(ScheduleSpec::Interval(secs)) if job.one_shot => {
+                format!("{}后发送一次", self.format_duration(secs))
+            }
+            Some(ScheduleSpec::Interval(secs)) => {
+                format!("每 {} 发送", self.format_duration(secs))
+            }
+            None => "规则无效".to_string(),
+        };
+        if job.pin {
+            description.push_str("，置顶");
+        }
+        if job.auto_delete {
+            description.push_str("，自动删除");
+        }
+        description
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScheduleSpec, parse_cron, translate_weekdays};
+    use chrono::{Datelike, Local, TimeZone, Timelike, Weekday};
+
+    #[test]
+    fn weekday_numbers_follow_standard_cron() {
+        assert_eq!(translate_weekdays("0").as_deref(), Some("1"));
+        assert_eq!(translate_weekdays("7").as_deref(), Some("1"));
+        assert_eq!(translate_weekdays("1-5").as_deref(), Some("2-6"));
+        assert_eq!(translate_weekdays("0-7").as_deref(), Some("1-7"));
+        assert_eq!(translate_weekdays("5-7").as_deref(), Some("6-7,1"));
+        assert_eq!(translate_weekdays("1,3/2").as_deref(), Some("2,4/2"));
+        assert_eq!(translate_weekdays("Mon-Fri").as_deref(), Some("Mon-Fri"));
+        assert_eq!(translate_weekdays("*").as_deref(), Some("*"));
+        assert_eq!(translate_weekdays("8"), None);
+        assert_eq!(translate_weekdays("5-7/2"), None);
+    }
+
+    #[test]
+    fn parse_cron_requires_five_fields() {
+        assert!(parse_cron("0 9 * * 1-5").is_some());
+        assert!(parse_cron("0 9 * *").is_none());
+        assert!(parse_cron("0 0 9 * * 1").is_none());
+        assert!(parse_cron("0 9 * * 9").is_none());
+    }
+
+    #[test]
+    fn cron_next_run_lands_on_the_right_weekday() {
+        // 2026-10-19 为周一
+        let now = Local.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let next_run = |expr: &str| {
+            let next = ScheduleSpec::Cron(expr.to_string())
+                .next_run(0, now.timestamp())
+                .unwrap();
+            Local.timestamp_opt(next, 0).unwrap()
+        };
+
+        let monday = next_run("0 9 * * 1");
+        assert_eq!(monday.weekday(), Weekday::Mon);
+        assert_eq!(monday.day(), 26);
+        assert_eq!((monday.hour(), monday.minute()), (9, 0));
+
+        assert_eq!(next_run("0 9 * * 0").weekday(), Weekday::Sun);
+        assert_eq!(next_run("0 9 * * 7").weekday(), Weekday::Sun);
+        assert_eq!(next_run("0 9 * * 6").weekday(), Weekday::Sat);
+        assert_eq!(next_run("30 8 * * 1-5").weekday(), Weekday::Tue);
+    }
+
+    #[test]
+    fn interval_next_run_skips_missed_runs() {
+        let spec = ScheduleSpec::Interval(60);
+        assert_eq!(spec.next_run(1000, 1010), Some(1060));
+        assert_eq!(spec.next_run(1000, 5000), Some(5060));
+    }
+}
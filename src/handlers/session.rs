@@ -0,0 +1,365 @@
+use super::permission::Permission;
+use super::{GLOBAL_GROUP_ID, MessageHandler};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::entities::group_role::{self, Entity as GroupRoleEntity};
+use crate::entities::group_setting::{self, Entity as GroupSettingEntity};
+use crate::entities::group_subscription::{self, Entity as GroupSubscriptionEntity};
+use crate::entities::user_session::{self, Entity as UserSessionEntity};
+use anyhow::Result;
+use frankenstein::AsyncTelegramApi;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::GetChatParams;
+use frankenstein::types::{
+    CallbackQuery, ChatType, InlineKeyboardButton, InlineKeyboardMarkup, MaybeInaccessibleMessage,
+    Message,
+};
+use sea_orm::{ActiveModelTrait, EntityTrait, QuerySelect, Set};
+use std::collections::BTreeSet;
+
+pub(super) const CALLBACK_PREFIX: &str = "session";
+
+// 通过 /start 深度链接进入私聊管理时使用的参数前缀
+const START_PAYLOAD_PREFIX: &str = "manage_";
+
+impl MessageHandler {
+    pub(super) fn is_private_chat(&self, message: &Message) -> bool {
+        message.chat.type_field == ChatType::Private
+    }
+
+    /// 确定命令操作的群组：群组中为当前群组，私聊中为会话所选择的群组
+    pub(super) async fn resolve_target_group(&self, message: &Message) -> Result<Option<i64>> {
+        if !self.is_private_chat(message) {
+            return Ok(Some(message.chat.id));
+        }
+
+        match &message.from {
+            Some(from) => self.session_group(from.id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// 查询用户在私聊中当前管理的群组
+    pub(super) async fn session_group(&self, user_id: u64) -> Result<Option<i64>> {
+        let session = UserSessionEntity::find_by_id(user_id as i64)
+            .one(&self.db)
+            .await?;
+
+        Ok(session.map(|session| session.group_id))
+    }
+
+    async fn set_session_group(&self, user_id: u64, group_id: i64) -> Result<()> {
+        let existing = UserSessionEntity::find_by_id(user_id as i64)
+            .one(&self.db)
+            .await?;
+
+        if let Some(existing) = existing {
+            let mut active_model: user_session::ActiveModel = existing.into();
+            active_model.group_id = Set(group_id);
+            active_model.update(&self.db).await?;
+        } else {
+            let session = user_session::ActiveModel {
+                user_id: Set(user_id as i64),
+                group_id: Set(group_id),
+            };
+            session.insert(&self.db).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 处理 /start，私聊中携带 `manage_<群组ID>` 参数时切换到该群组
+    pub(super) async fn handle_start_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+    ) -> Result<()> {
+        if !self.is_private_chat(message) {
+            return Ok(());
+        }
+
+        let content = self.get_content_after_command(message, command)?;
+        let group_id = content
+            .trim()
+            .strip_prefix(START_PAYLOAD_PREFIX)
+            .and_then(|id| id.parse::<i64>().ok());
+
+        match group_id {
+            Some(group_id) => self.select_group(&api, message, group_id).await?,
+            None => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    "在群组中发送 /manage，或在此发送 /groups 选择要管理的群组，之后即可在私聊中使用关键词管理命令",
+                    None,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 在群组中发送跳转到私聊的按钮，私聊中等同于 /groups
+    pub(super) async fn handle_manage_command(&self, api: Bot, message: &Message) -> Result<()> {
+        if self.is_private_chat(message) {
+            return self.handle_groups_command(api, message).await;
+        }
+
+        if !self
+            .check_permission(&api, message, message.chat.id, Permission::Edit)
+            .await?
+        {
+            return Ok(());
+        }
+
+        let username = api.get_me().await?.result.username.unwrap_or_default();
+        let url = format!(
+            "https://t.me/{}?start={}{}",
+            username, START_PAYLOAD_PREFIX, message.chat.id
+        );
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(vec![vec![
+                InlineKeyboardButton::builder()
+                    .text("在私聊中管理")
+                    .url(url)
+                    .build(),
+            ]])
+            .build();
+
+        self.send_reply_with_markup(
+            api,
+            message.chat.id,
+            "点击下方按钮，在与机器人的私聊中管理本群的关键词",
+            None,
+            Some(keyboard),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 列出用户拥有角色、且机器人已记录过的群组
+    pub(super) async fn handle_groups_command(&self, api: Bot, message: &Message) -> Result<()> {
+        if !self.is_private_chat(message) {
+            self.send_reply(api, message.chat.id, "请在与机器人的私聊中使用此命令", None)
+                .await?;
+            return Ok(());
+        }
+        let Some(from) = &message.from else {
+            return Ok(());
+        };
+
+        let mut rows = Vec::new();
+        for group_id in self.known_groups().await? {
+            if self.user_role(&api, group_id, from.id).await?.is_none() {
+                continue;
+            }
+            let title = self.group_title(&api, group_id).await;
+            rows.push(vec![
+                InlineKeyboardButton::builder()
+                    .text(title)
+                    .callback_data(format!("{}:{}", CALLBACK_PREFIX, group_id))
+                    .build(),
+            ]);
+        }
+
+        if rows.is_empty() {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "没有找到你可以管理的群组，请在群组中发送 /manage",
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(rows)
+            .build();
+        self.send_reply_with_markup(
+            api,
+            message.chat.id,
+            "请选择要管理的群组:",
+            None,
+            Some(keyboard),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 处理 /use，带群组 ID 时切换群组，否则显示当前管理的群组
+    pub(super) async fn handle_use_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+    ) -> Result<()> {
+        if !self.is_private_chat(message) {
+            self.send_reply(api, message.chat.id, "请在与机器人的私聊中使用此命令", None)
+                .await?;
+            return Ok(());
+        }
+        let Some(from) = &message.from else {
+            return Ok(());
+        };
+
+        let content = self.get_content_after_command(message, command)?;
+        let content = content.trim();
+        if !content.is_empty() {
+            let Ok(group_id) = content.parse::<i64>() else {
+                self.send_reply(api, message.chat.id, "用法: /use [群组ID]", None)
+                    .await?;
+                return Ok(());
+            };
+            return self.select_group(&api, message, group_id).await;
+        }
+
+        let text = match self.session_group(from.id).await? {
+            Some(group_id) => format!(
+                "当前管理的群组: {} (<code>{}</code>)",
+                self.escape_html(&self.group_title(&api, group_id).await),
+                group_id
+            ),
+            None => "尚未选择要管理的群组，请使用 /groups 选择".to_string(),
+        };
+        self.send_reply(api, message.chat.id, &text, None).await?;
+
+        Ok(())
+    }
+
+    /// 处理 /groups 列表中的选择按钮，回调数据格式为 `session:<群组ID>`
+    pub(super) async fn handle_session_callback(
+        &self,
+        api: &Bot,
+        query: &CallbackQuery,
+        args: &str,
+    ) -> Result<()> {
+        let Some(MaybeInaccessibleMessage::Message(message)) = &query.message else {
+            self.answer_callback(api, query, Some("消息已过期")).await?;
+            return Ok(());
+        };
+        let Ok(group_id) = args.parse::<i64>() else {
+            self.answer_callback(api, query, None).await?;
+            return Ok(());
+        };
+
+        if self
+            .user_role(api, group_id, query.from.id)
+            .await?
+            .is_none()
+        {
+            self.answer_callback(api, query, Some("你在该群组中没有任何角色"))
+                .await?;
+            return Ok(());
+        }
+
+        self.set_session_group(query.from.id, group_id).await?;
+        let title = self.group_title(api, group_id).await;
+        self.answer_callback(api, query, Some(&format!("已切换到 {}", title)))
+            .await?;
+        self.send_reply(
+            api.clone(),
+            message.chat.id,
+            &self.format_session_switched(&title, group_id),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 确认发送者在目标群组中拥有角色后切换私聊会话
+    async fn select_group(&self, api: &Bot, message: &Message, group_id: i64) -> Result<()> {
+        let Some(from) = &message.from else {
+            return Ok(());
+        };
+
+        if group_id == GLOBAL_GROUP_ID || self.user_role(api, group_id, from.id).await?.is_none() {
+            self.send_reply(
+                api.clone(),
+                message.chat.id,
+                "你在该群组中没有任何角色，或机器人不在该群组中",
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        self.set_session_group(from.id, group_id).await?;
+        let title = self.group_title(api, group_id).await;
+        self.send_reply(
+            api.clone(),
+            message.chat.id,
+            &self.format_session_switched(&title, group_id),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    fn format_session_switched(&self, title: &str, group_id: i64) -> String {
+        format!(
+            "已切换到群组 {} (<code>{}</code>)，现在可以直接在私聊中使用关键词管理命令",
+            self.escape_html(title),
+            group_id
+        )
+    }
+
+    /// 数据库中出现过的所有群组
+    pub(super) async fn known_groups(&self) -> Result<BTreeSet<i64>> {
+        let mut groups = BTreeSet::new();
+        groups.extend(
+            GroupReplyEntity::find()
+                .select_only()
+                .column(group_reply::Column::GroupId)
+                .distinct()
+                .into_tuple::<i64>()
+                .all(&self.db)
+                .await?,
+        );
+        groups.extend(
+            GroupSettingEntity::find()
+                .select_only()
+                .column(group_setting::Column::GroupId)
+                .into_tuple::<i64>()
+                .all(&self.db)
+                .await?,
+        );
+        groups.extend(
+            GroupSubscriptionEntity::find()
+                .select_only()
+                .column(group_subscription::Column::GroupId)
+                .into_tuple::<i64>()
+                .all(&self.db)
+                .await?,
+        );
+        groups.extend(
+            GroupRoleEntity::find()
+                .select_only()
+                .column(group_role::Column::GroupId)
+                .distinct()
+                .into_tuple::<i64>()
+                .all(&self.db)
+                .await?,
+        );
+        groups.remove(&GLOBAL_GROUP_ID);
+
+        Ok(groups)
+    }
+
+    /// 获取群组名称，获取失败时显示群组 ID
+    async fn group_title(&self, api: &Bot, group_id: i64) -> String {
+        let params = GetChatParams::builder().chat_id(group_id).build();
+        match api.get_chat(&params).await {
+            Ok(response) => response
+                .result
+                .title
+                .unwrap_or_else(|| group_id.to_string()),
+            Err(_) => group_id.to_string(),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::MessageHandler;
+use crate::entities::group_setting::{self, Entity as GroupSettingEntity};
+use anyhow::Result;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
+
+impl MessageHandler {
+    /// 查询群组设置，未设置过的群组返回默认值
+    pub(super) async fn load_group_setting(&self, group_id: i64) -> Result<group_setting::Model> {
+        let setting = GroupSettingEntity::find_by_id(group_id)
+            .one(&self.db)
+            .await?
+            .unwrap_or(group_setting::Model {
+                group_id,
+                global_disabled: false,
+                list_restricted: false,
+                keyword_cooldown_secs: 0,
+                user_rate_limit: 0,
+                ignore_bots: false,
+                ignore_forwards: false,
+                ignore_channel_posts: false,
+                warn_mute_threshold: 3,
+                warn_ban_threshold: 5,
+                warn_mute_secs: 24 * 60 * 60,
+                welcome_message: None,
+                goodbye_message: None,
+                captcha_enabled: false,
+                captcha_mode: "button".to_string(),
+                captcha_timeout_secs: 120,
+            });
+
+        Ok(setting)
+    }
+
+    /// 修改群组设置，记录不存在时按默认值创建
+    pub(super) async fn update_group_setting<F>(&self, group_id: i64, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut group_setting::ActiveModel),
+    {
+        let existing = GroupSettingEntity::find_by_id(group_id)
+            .one(&self.db)
+            .await?;
+
+        if let Some(existing) = existing {
+            let mut active_model = existing.into_active_model();
+            update(&mut active_model);
+            active_model.update(&self.db).await?;
+        } else {
+            let mut active_model = self
+                .load_group_setting(group_id)
+                .await?
+                .into_active_model()
+                .reset_all();
+            active_model.group_id = Set(group_id);
+            update(&mut active_model);
+            active_model.insert(&self.db).await?;
+        }
+
+        Ok(())
+    }
+}
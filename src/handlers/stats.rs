@@ -0,0 +1,350 @@
+use super::MessageHandler;
+use crate::entities::daily_hit::{self, Entity as DailyHitEntity};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::entities::keyword_stat::{self, Entity as KeywordStatEntity};
+use anyhow::Result;
+use chrono::{Days, Local};
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, Set,
+    TransactionTrait,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// /stats 中列出的常用关键词数量
+const TOP_KEYWORDS_SHOWN: usize = 10;
+
+// /stats 中最多列出的从未触发的关键词数量
+const MAX_UNUSED_SHOWN: usize = 20;
+
+// /stats 中显示每日触发次数的天数
+const DAILY_HIT_DAYS: u64 = 7;
+
+struct KeywordHits {
+    hits: i64,
+    last_hit_at: i64,
+}
+
+#[derive(Default)]
+struct PendingHits {
+    keywords: HashMap<(i64, String), KeywordHits>,
+    days: HashMap<(i64, String), i64>,
+}
+
+/// 尚未写入数据库的命中计数
+///
+/// 回复关键词时只在内存中累加，由后台任务定期批量写入，避免每条消息都写一次数据库
+#[derive(Clone, Default)]
+pub(super) struct HitCounter {
+    pending: Arc<Mutex<PendingHits>>,
+}
+
+impl HitCounter {
+    fn record(&self, group_id: i64, keywords: &str) {
+        let now = Local::now();
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let entry = pending
+            .keywords
+            .entry((group_id, keywords.to_string()))
+            .or_insert(KeywordHits {
+                hits: 0,
+                last_hit_at: 0,
+            });
+        entry.hits += 1;
+        entry.last_hit_at = now.timestamp();
+        *pending
+            .days
+            .entry((group_id, now.format("%Y-%m-%d").to_string()))
+            .or_default() += 1;
+    }
+
+    fn take(&self) -> PendingHits {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::take(&mut *pending)
+    }
+
+    /// 写入失败时把取出的计数合并回去，等下次再写入
+    fn restore(&self, taken: PendingHits) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        for (key, hits) in taken.keywords {
+            let entry = pending.keywords.entry(key).or_insert(KeywordHits {
+                hits: 0,
+                last_hit_at: 0,
+            });
+            entry.hits += hits.hits;
+            entry.last_hit_at = entry.last_hit_at.max(hits.last_hit_at);
+        }
+        for (key, hits) in taken.days {
+            *pending.days.entry(key).or_default() += hits;
+        }
+    }
+
+    /// 关键词改名后，尚未写入的计数也改记到新名称下
+    fn rename(&self, group_id: i64, old_keywords: &str, new_keywords: &str) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(hits) = pending
+            .keywords
+            .remove(&(group_id, old_keywords.to_string()))
+        {
+            pending
+                .keywords
+                .insert((group_id, new_keywords.to_string()), hits);
+        }
+    }
+
+    /// 丢弃被删除关键词尚未写入的计数，keywords 为 None 时丢弃整个群组的
+    fn remove(&self, group_id: i64, keywords: Option<&str>) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        pending
+            .keywords
+            .retain(|(id, name), _| *id != group_id || keywords.is_some_and(|k| k != name));
+    }
+}
+
+impl MessageHandler {
+    /// 记录一次关键词触发，统计数据按触发所在的群组计数
+    pub(super) fn record_keyword_hit(&self, group_id: i64, keywords: &str) {
+        self.hit_counter.record(group_id, keywords);
+    }
+
+    /// 将内存中累计的命中次数在一个事务中写入数据库
+    pub async fn flush_keyword_hits(&self) -> Result<()> {
+        let pending = self.hit_counter.take();
+        if pending.keywords.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.write_keyword_hits(&pending).await {
+            self.hit_counter.restore(pending);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    async fn write_keyword_hits(&self, pending: &PendingHits) -> Result<()> {
+        let txn = self.db.begin().await?;
+        for ((group_id, keywords), hits) in &pending.keywords {
+            match KeywordStatEntity::find_by_id((*group_id, keywords.clone()))
+                .one(&txn)
+                .await?
+            {
+                Some(stat) => {
+                    let total = stat.hits + hits.hits;
+                    let mut active_model = stat.into_active_model();
+                    active_model.hits = Set(total);
+                    active_model.last_hit_at = Set(hits.last_hit_at);
+                    active_model.update(&txn).await?;
+                }
+                None => {
+                    let stat = keyword_stat::ActiveModel {
+                        group_id: Set(*group_id),
+                        keywords: Set(keywords.clone()),
+                        hits: Set(hits.hits),
+                        last_hit_at: Set(hits.last_hit_at),
+                    };
+                    stat.insert(&txn).await?;
+                }
+            }
+        }
+        for ((group_id, day), hits) in &pending.days {
+            match DailyHitEntity::find_by_id((*group_id, day.clone()))
+                .one(&txn)
+                .await?
+            {
+                Some(daily) => {
+                    let total = daily.hits + hits;
+                    let mut active_model = daily.into_active_model();
+                    active_model.hits = Set(total);
+                    active_model.update(&txn).await?;
+                }
+                None => {
+                    let daily = daily_hit::ActiveModel {
+                        group_id: Set(*group_id),
+                        day: Set(day.clone()),
+                        hits: Set(*hits),
+                    };
+                    daily.insert(&txn).await?;
+                }
+            }
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// 关键词改名时迁移其统计，与改名在同一事务中执行
+    pub(super) async fn rename_keyword_stat<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        old_keywords: &str,
+        new_keywords: &str,
+    ) -> Result<()> {
+        // 新名称可能残留已删除关键词的统计，先清除以免主键冲突
+        KeywordStatEntity::delete_by_id((group_id, new_keywords.to_string()))
+            .exec(db)
+            .await?;
+        KeywordStatEntity::update_many()
+            .col_expr(keyword_stat::Column::Keywords, Expr::value(new_keywords))
+            .filter(keyword_stat::Column::GroupId.eq(group_id))
+            .filter(keyword_stat::Column::Keywords.eq(old_keywords))
+            .exec(db)
+            .await?;
+        self.hit_counter
+            .rename(group_id, old_keywords, new_keywords);
+
+        Ok(())
+    }
+
+    /// 删除关键词的统计，keywords 为 None 时删除群组的全部关键词统计
+    pub(super) async fn delete_keyword_stats<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        keywords: Option<&str>,
+    ) -> Result<()> {
+        let mut delete =
+            KeywordStatEntity::delete_many().filter(keyword_stat::Column::GroupId.eq(group_id));
+        if let Some(keywords) = keywords {
+            delete = delete.filter(keyword_stat::Column::Keywords.eq(keywords));
+        }
+        delete.exec(db).await?;
+        self.hit_counter.remove(group_id, keywords);
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_stats_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        // 先写入尚未落盘的计数，保证统计是最新的
+        self.flush_keyword_hits().await?;
+
+        let mut stats = KeywordStatEntity::find()
+            .filter(keyword_stat::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?;
+        stats.sort_by(|a, b| b.hits.cmp(&a.hits).then(b.last_hit_at.cmp(&a.last_hit_at)));
+
+        let mut text = String::from("<b>关键词统计</b>\n");
+
+        text.push_str("\n<b>最常触发:</b>\n");
+        if stats.is_empty() {
+            text.push_str("暂无触发记录\n");
+        }
+        for (index, stat) in stats.iter().take(TOP_KEYWORDS_SHOWN).enumerate() {
+            text.push_str(&format!(
+                "{}. <code>{}</code> {} 次，最近 {}\n",
+                index + 1,
+                self.escape_html(&stat.keywords),
+                stat.hits,
+                self.format_timestamp(stat.last_hit_at)
+            ));
+        }
+
+        // 只统计本群自己的关键词，继承来的关键词不在此列
+        let used: HashSet<&str> = stats.iter().map(|stat| stat.keywords.as_str()).collect();
+        let unused: Vec<String> = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .filter(|reply| !used.contains(reply.keywords.as_str()))
+            .map(|reply| format!("<code>{}</code>", self.escape_html(&reply.keywords)))
+            .collect();
+        text.push_str(&format!("\n<b>从未触发 ({}):</b>\n", unused.len()));
+        if unused.is_empty() {
+            text.push_str("无\n");
+        } else {
+            text.push_str(&unused[..unused.len().min(MAX_UNUSED_SHOWN)].join(", "));
+            if unused.len() > MAX_UNUSED_SHOWN {
+                text.push_str(&format!(" 等 {} 个", unused.len()));
+            }
+            text.push('\n');
+        }
+
+        let today = Local::now().date_naive();
+        let days: Vec<String> = (0..DAILY_HIT_DAYS)
+            .filter_map(|offset| today.checked_sub_days(Days::new(offset)))
+            .map(|day| day.format("%Y-%m-%d").to_string())
+            .collect();
+        let daily_hits: HashMap<String, i64> = DailyHitEntity::find()
+            .filter(daily_hit::Column::GroupId.eq(group_id))
+            .filter(daily_hit::Column::Day.is_in(days.clone()))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|daily| (daily.day, daily.hits))
+            .collect();
+        text.push_str(&format!(
+            "\n<b>最近 {} 天每日触发次数:</b>\n",
+            DAILY_HIT_DAYS
+        ));
+        for day in &days {
+            text.push_str(&format!(
+                "{} {}\n",
+                day,
+                daily_hits.get(day).copied().unwrap_or(0)
+            ));
+        }
+
+        self.send_reply(api, message.chat.id, text.trim_end(), None)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HitCounter;
+
+    fn keyword_hits(counter: &HitCounter, group_id: i64, keywords: &str) -> Option<i64> {
+        let pending = counter.pending.lock().unwrap();
+        pending
+            .keywords
+            .get(&(group_id, keywords.to_string()))
+            .map(|hits| hits.hits)
+    }
+
+    #[test]
+    fn restore_merges_taken_hits_back() {
+        let counter = HitCounter::default();
+        counter.record(1, "群规");
+        counter.record(1, "群规");
+        let taken = counter.take();
+        counter.record(1, "群规");
+        counter.restore(taken);
+
+        assert_eq!(keyword_hits(&counter, 1, "群规"), Some(3));
+        let pending = counter.pending.lock().unwrap();
+        assert_eq!(pending.days.values().sum::<i64>(), 3);
+    }
+
+    #[test]
+    fn rename_and_remove_follow_keyword_changes() {
+        let counter = HitCounter::default();
+        counter.record(1, "群规");
+        counter.record(1, "价格");
+        counter.record(2, "群规");
+
+        counter.rename(1, "群规", "规则");
+        assert_eq!(keyword_hits(&counter, 1, "群规"), None);
+        assert_eq!(keyword_hits(&counter, 1, "规则"), Some(1));
+
+        counter.remove(1, Some("价格"));
+        assert_eq!(keyword_hits(&counter, 1, "价格"), None);
+        assert_eq!(keyword_hits(&counter, 1, "规则"), Some(1));
+
+        counter.remove(1, None);
+        assert_eq!(keyword_hits(&counter, 1, "规则"), None);
+        assert_eq!(keyword_hits(&counter, 2, "群规"), Some(1));
+    }
+}
@@ -0,0 +1,166 @@
+use super::MessageHandler;
+use super::permission::{Permission, Role};
+use super::transfer::ImportMode;
+use crate::entities::group_subscription::{self, Entity as GroupSubscriptionEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+impl MessageHandler {
+    pub(super) async fn handle_copyfrom_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let mut parts = content.split_whitespace();
+        let source = parts.next().and_then(|id| id.parse::<i64>().ok());
+        let mode = match parts.next() {
+            None | Some("merge") => Some(ImportMode::Merge),
+            Some("replace") => Some(ImportMode::Replace),
+            Some(_) => None,
+        };
+        let (Some(source_group_id), Some(mode)) = (source, mode) else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "用法: /copyfrom &lt;群组ID&gt; [merge|replace]",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        if !self
+            .check_source_group(&api, message, group_id, source_group_id)
+            .await?
+        {
+            return Ok(());
+        }
+
+        let records = self.export_keyword_records(source_group_id).await?;
+        if records.is_empty() {
+            self.send_reply(api, message.chat.id, "来源群组没有设置任何关键词", None)
+                .await?;
+            return Ok(());
+        }
+
+        // 关键词动作会禁言或封禁成员，只有群组管理员才能随关键词一起复制
+        let allow_actions = self
+            .sender_has_permission(&api, message, group_id, Permission::Moderate)
+            .await?;
+        self.import_or_confirm(api, message, group_id, records, mode, allow_actions)
+            .await
+    }
+
+    pub(super) async fn handle_subscribe_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let Ok(source_group_id) = content.trim().parse::<i64>() else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "用法: /subscribe &lt;群组ID&gt;",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        if !self
+            .check_source_group(&api, message, group_id, source_group_id)
+            .await?
+        {
+            return Ok(());
+        }
+
+        // 每个群组只能继承一个来源群组，重复订阅时替换原来源
+        let existing = GroupSubscriptionEntity::find_by_id(group_id)
+            .one(&self.db)
+            .await?;
+        if let Some(existing) = existing {
+            let mut active_model: group_subscription::ActiveModel = existing.into();
+            active_model.source_group_id = Set(source_group_id);
+            active_model.update(&self.db).await?;
+        } else {
+            let subscription = group_subscription::ActiveModel {
+                group_id: Set(group_id),
+                source_group_id: Set(source_group_id),
+            };
+            subscription.insert(&self.db).await?;
+        }
+
+        self.send_reply(
+            api,
+            message.chat.id,
+            &format!(
+                "已订阅群组 <code>{}</code> 的关键词，本群的同名关键词优先生效",
+                source_group_id
+            ),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_unsubscribe_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let result = GroupSubscriptionEntity::delete_by_id(group_id)
+            .exec(&self.db)
+            .await?;
+        let message_text = if result.rows_affected > 0 {
+            "已取消订阅，不再继承其他群组的关键词"
+        } else {
+            "当前群组没有订阅其他群组"
+        };
+        self.send_reply(api, message.chat.id, message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 检查来源群组是否有效，并确认发送者在来源群组中同样拥有编辑权限
+    async fn check_source_group(
+        &self,
+        api: &Bot,
+        message: &Message,
+        group_id: i64,
+        source_group_id: i64,
+    ) -> Result<bool> {
+        if source_group_id == group_id {
+            self.send_reply(api.clone(), message.chat.id, "来源群组不能是当前群组", None)
+                .await?;
+            return Ok(false);
+        }
+
+        let Some(from) = &message.from else {
+            return Ok(false);
+        };
+        let source_role = self.user_role(api, source_group_id, from.id).await?;
+        if source_role < Some(Role::Editor) {
+            self.send_reply(
+                api.clone(),
+                message.chat.id,
+                "你在来源群组中没有编辑权限，或机器人不在该群组中",
+                None,
+            )
+            .await?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
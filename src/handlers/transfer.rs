@@ -0,0 +1,670 @@
+use super::MessageHandler;
+use super::audit::Operation;
+use super::moderation::KeywordAction;
+use super::permission::Permission;
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::entities::keyword_alias::{self, Entity as KeywordAliasEntity};
+use anyhow::{Result, anyhow};
+use frankenstein::AsyncTelegramApi;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::input_file::InputFile;
+use frankenstein::methods::{GetFileParams, SendDocumentParams};
+use frankenstein::types::Message;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 导入文件的大小上限
+const MAX_IMPORT_FILE_BYTES: u64 = 1024 * 1024;
+
+// 导入摘要中最多列出的跳过原因条数
+const MAX_SKIPPED_SHOWN: usize = 10;
+
+const CSV_HEADER: [&str; 8] = [
+    "keywords",
+    "reply",
+    "aliases",
+    "exclude_words",
+    "require_words",
+    "cooldown_secs",
+    "action",
+    "action_duration_secs",
+];
+
+/// 导出文件中的一条关键词，包含全部元数据
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct KeywordRecord {
+    pub keywords: String,
+    pub reply: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub exclude_words: Option<String>,
+    #[serde(default)]
+    pub require_words: Option<String>,
+    #[serde(default)]
+    pub cooldown_secs: Option<i32>,
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub action_duration_secs: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeywordExport {
+    group_id: i64,
+    keywords: Vec<KeywordRecord>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// 导入模式：合并保留群组中已有的其他关键词，替换则先清空
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum ImportMode {
+    Merge,
+    Replace,
+}
+
+#[derive(Default)]
+pub(super) struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: Vec<String>,
+    /// 因导入者不是群组管理员而未导入关键词动作的条目数
+    pub actions_dropped: usize,
+}
+
+impl MessageHandler {
+    pub(super) async fn handle_export_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let format = match content.trim() {
+            "" | "json" => ExportFormat::Json,
+            "csv" => ExportFormat::Csv,
+            _ => {
+                self.send_reply(api, message.chat.id, "用法: /export [json|csv]", None)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let records = self.export_keyword_records(group_id).await?;
+        if records.is_empty() {
+            self.send_reply(api, message.chat.id, "当前群组没有设置任何关键词", None)
+                .await?;
+            return Ok(());
+        }
+
+        let count = records.len();
+        let (body, extension) = match format {
+            ExportFormat::Json => (
+                serde_json::to_string_pretty(&KeywordExport {
+                    group_id,
+                    keywords: records,
+                })?,
+                "json",
+            ),
+            ExportFormat::Csv => (self.records_to_csv(&records), "csv"),
+        };
+
+        // 文件上传只支持本地路径，先写入临时目录
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let path =
+            std::env::temp_dir().join(format!("keywords_{}_{}.{}", group_id, timestamp, extension));
+        tokio::fs::write(&path, body).await?;
+
+        let params = SendDocumentParams::builder()
+            .chat_id(message.chat.id)
+            .document(InputFile::from(path.clone()))
+            .caption(format!("已导出 {} 个关键词", count))
+            .build();
+        let result = api.send_document(&params).await;
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            eprintln!("删除临时导出文件时出错: {}", e);
+        }
+        if let Err(e) = result {
+            eprintln!("发送导出文件时出错: {}", e);
+            self.send_reply(api, message.chat.id, "发送导出文件失败，请稍后重试", None)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_import_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let mode = match content.trim() {
+            "" | "merge" => ImportMode::Merge,
+            "replace" => ImportMode::Replace,
+            _ => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    "用法: 回复导出的 JSON 或 CSV 文件发送 /import [merge|replace]",
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let Some(document) = message
+            .reply_to_message
+            .as_ref()
+            .and_then(|reply| reply.document.as_ref())
+        else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                "请回复一个导出的 JSON 或 CSV 文件使用此命令",
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        if document.file_size.unwrap_or(0) > MAX_IMPORT_FILE_BYTES {
+            self.send_reply(api, message.chat.id, "文件过大，最大支持 1 MB", None)
+                .await?;
+            return Ok(());
+        }
+
+        let records = match self.download_records(&api, &document.file_id).await {
+            Ok(records) => records,
+            Err(e) => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!("无法解析导入文件: {}", self.escape_html(&e.to_string())),
+                    None,
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        // 关键词动作会禁言或封禁成员，只有群组管理员才能随关键词一起导入
+        let allow_actions = self
+            .sender_has_permission(&api, message, group_id, Permission::Moderate)
+            .await?;
+        self.import_or_confirm(api, message, group_id, records, mode, allow_actions)
+            .await
+    }
+
+    pub(super) async fn export_keyword_records(&self, group_id: i64) -> Result<Vec<KeywordRecord>> {
+        let replies = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .all(&self.db)
+            .await?;
+        let mut aliases = self.load_aliases(group_id).await?;
+
+        let records = replies
+            .into_iter()
+            .map(|reply| KeywordRecord {
+                aliases: aliases.remove(&reply.keywords).unwrap_or_default(),
+                keywords: reply.keywords,
+                reply: reply.reply,
+                exclude_words: reply.exclude_words,
+                require_words: reply.require_words,
+                cooldown_secs: reply.cooldown_secs,
+                action: reply.action,
+                action_duration_secs: reply.action_duration_secs,
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    /// 在一个事务中导入关键词，无效或与现有内容相同的条目计为跳过
+    ///
+    /// `allow_actions` 为 false 时忽略记录中的关键词动作，已有关键词保留原来的动作
+    pub(super) async fn import_keyword_records(
+        &self,
+        group_id: i64,
+        operation: Operation,
+        records: Vec<KeywordRecord>,
+        mode: ImportMode,
+        allow_actions: bool,
+    ) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let txn = self.db.begin().await?;
+
+        if mode == ImportMode::Replace {
+            self.delete_all_keywords(&txn, group_id, operation).await?;
+        }
+
+        for (index, record) in records.into_iter().enumerate() {
+            let keywords = record.keywords.trim().to_string();
+            if keywords.is_empty() || keywords.contains(char::is_whitespace) {
+                summary
+                    .skipped
+                    .push(format!("第 {} 条: 关键词为空或包含空白字符", index + 1));
+                continue;
+            }
+            if record.reply.trim().is_empty() {
+                summary.skipped.push(format!(
+                    "第 {} 条: <code>{}</code> 回复内容为空",
+                    index + 1,
+                    self.escape_html(&keywords)
+                ));
+                continue;
+            }
+
+            // 已是其他关键词的别名时跳过，避免新关键词遮住该别名
+            let alias_owner = KeywordAliasEntity::find()
+                .filter(keyword_alias::Column::GroupId.eq(group_id))
+                .filter(keyword_alias::Column::Alias.eq(&keywords))
+                .one(&txn)
+                .await?;
+            if let Some(alias_owner) = alias_owner {
+                summary.skipped.push(format!(
+                    "第 {} 条: <code>{}</code> 已是关键词 <code>{}</code> 的别名",
+                    index + 1,
+                    self.escape_html(&keywords),
+                    self.escape_html(&alias_owner.keywords)
+                ));
+                continue;
+            }
+
+            let exclude_words = record
+                .exclude_words
+                .map(|words| self.split_triggers(&words).join("|"))
+                .filter(|words| !words.is_empty());
+            let require_words = record
+                .require_words
+                .map(|words| self.split_triggers(&words).join("|"))
+                .filter(|words| !words.is_empty());
+            let cooldown_secs = record.cooldown_secs.filter(|secs| *secs >= 0);
+            // 无法识别的动作按未设置处理
+            let mut action = record
+                .action
+                .filter(|action| action.parse::<KeywordAction>().is_ok());
+            let mut action_duration_secs = record
+                .action_duration_secs
+                .filter(|secs| action.is_some() && *secs > 0);
+
+            let existing = GroupReplyEntity::find()
+                .filter(group_reply::Column::GroupId.eq(group_id))
+                .filter(group_reply::Column::Keywords.eq(&keywords))
+                .one(&txn)
+                .await?;
+            if !allow_actions {
+                let kept_action = existing.as_ref().and_then(|e| e.action.clone());
+                let kept_duration = existing.as_ref().and_then(|e| e.action_duration_secs);
+                if (&action, action_duration_secs) != (&kept_action, kept_duration) {
+                    summary.actions_dropped += 1;
+                }
+                action = kept_action;
+                action_duration_secs = kept_duration;
+            }
+            if let Some(existing) = &existing {
+                let aliases: Vec<String> = KeywordAliasEntity::find()
+                    .filter(keyword_alias::Column::GroupId.eq(group_id))
+                    .filter(keyword_alias::Column::Keywords.eq(&keywords))
+                    .all(&txn)
+                    .await?
+                    .into_iter()
+                    .map(|alias| alias.alias)
+                    .collect();
+                if existing.reply == record.reply
+                    && existing.exclude_words == exclude_words
+                    && existing.require_words == require_words
+                    && existing.cooldown_secs == cooldown_secs
+                    && existing.action == action
+                    && existing.action_duration_secs == action_duration_secs
+                    && record.aliases.iter().all(|alias| aliases.contains(alias))
+                {
+                    summary.skipped.push(format!(
+                        "第 {} 条: <code>{}</code> 内容未变化",
+                        index + 1,
+                        self.escape_html(&keywords)
+                    ));
+                    continue;
+                }
+            }
+
+            let is_updated = self
+                .add_keyword_reply(&txn, group_id, operation, keywords.clone(), record.reply)
+                .await?;
+            let reply = GroupReplyEntity::find()
+                .filter(group_reply::Column::GroupId.eq(group_id))
+                .filter(group_reply::Column::Keywords.eq(&keywords))
+                .one(&txn)
+                .await?
+                .ok_or_else(|| anyhow!("导入的关键词写入后未找到"))?;
+            let mut active_model: group_reply::ActiveModel = reply.into();
+            active_model.exclude_words = Set(exclude_words);
+            active_model.require_words = Set(require_words);
+            active_model.cooldown_secs = Set(cooldown_secs);
+            active_model.action = Set(action);
+            active_model.action_duration_secs = Set(action_duration_secs);
+            active_model.update(&txn).await?;
+
+            let aliases = self.split_triggers(&record.aliases.join("|"));
+            for alias in self
+                .add_keyword_aliases(&txn, group_id, &keywords, &aliases)
+                .await?
+            {
+                summary.skipped.push(format!(
+                    "第 {} 条: 别名 <code>{}</code> 与已有关键词冲突",
+                    index + 1,
+                    self.escape_html(&alias)
+                ));
+            }
+
+            if is_updated {
+                summary.updated += 1;
+            } else {
+                summary.added += 1;
+            }
+        }
+
+        txn.commit().await?;
+        Ok(summary)
+    }
+
+    pub(super) fn format_import_summary(&self, summary: &ImportSummary) -> String {
+        let mut text = format!(
+            "导入完成！新增 {} 个，更新 {} 个，跳过 {} 项",
+            summary.added,
+            summary.updated,
+            summary.skipped.len()
+        );
+        if summary.actions_dropped > 0 {
+            text.push_str(&format!(
+                "\n{} 条记录的关键词动作未导入，只有群组管理员可以导入关键词动作",
+                summary.actions_dropped
+            ));
+        }
+        for reason in summary.skipped.iter().take(MAX_SKIPPED_SHOWN) {
+            text.push_str(&format!("\n{}", reason));
+        }
+        if summary.skipped.len() > MAX_SKIPPED_SHOWN {
+            text.push_str(&format!(
+                "\n……另有 {} 项未列出",
+                summary.skipped.len() - MAX_SKIPPED_SHOWN
+            ));
+        }
+        text
+    }
+
+    /// 通过 get_file 下载文件并解析为关键词记录
+    pub(super) async fn download_records(
+        &self,
+        api: &Bot,
+        file_id: &str,
+    ) -> Result<Vec<KeywordRecord>> {
+        let params = GetFileParams::builder().file_id(file_id).build();
+        let file = api.get_file(&params).await?.result;
+        let file_path = file
+            .file_path
+            .ok_or_else(|| anyhow!("Telegram 未返回文件路径"))?;
+
+        // 文件下载地址为 https://api.telegram.org/file/bot<token>/<file_path>
+        let url = format!(
+            "{}/{}",
+            api.api_url.replacen("/bot", "/file/bot", 1),
+            file_path
+        );
+        let mut response = api
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("下载文件失败: {}", e.without_url()))?;
+
+        // 消息中的文件大小可能缺失，按实际下载的内容再限制一次
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| anyhow!("读取文件失败: {}", e.without_url()))?
+        {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > MAX_IMPORT_FILE_BYTES {
+                return Err(anyhow!("文件过大，最大支持 1 MB"));
+            }
+        }
+        let body = String::from_utf8(body).map_err(|_| anyhow!("文件不是 UTF-8 编码"))?;
+
+        self.parse_records(&body)
+    }
+
+    /// 解析导出文件的内容，先尝试 JSON 再尝试 CSV
+    fn parse_records(&self, body: &str) -> Result<Vec<KeywordRecord>> {
+        let body = body.trim_start_matches('\u{feff}');
+        if body.trim_start().starts_with('{') {
+            let export: KeywordExport =
+                serde_json::from_str(body).map_err(|e| anyhow!("JSON 格式错误: {}", e))?;
+            Ok(export.keywords)
+        } else if body.trim_start().starts_with('[') {
+            serde_json::from_str(body).map_err(|e| anyhow!("JSON 格式错误: {}", e))
+        } else {
+            self.csv_to_records(body)
+        }
+    }
+
+    fn records_to_csv(&self, records: &[KeywordRecord]) -> String {
+        let mut csv = CSV_HEADER.join(",");
+        csv.push_str("\r\n");
+        for record in records {
+            let fields = [
+                record.keywords.as_str(),
+                record.reply.as_str(),
+                &record.aliases.join("|"),
+                record.exclude_words.as_deref().unwrap_or(""),
+                record.require_words.as_deref().unwrap_or(""),
+                &record
+                    .cooldown_secs
+                    .map(|secs| secs.to_string())
+                    .unwrap_or_default(),
+                record.action.as_deref().unwrap_or(""),
+                &record
+                    .action_duration_secs
+                    .map(|secs| secs.to_string())
+                    .unwrap_or_default(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| self.csv_escape(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    fn csv_escape(&self, field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn csv_to_records(&self, body: &str) -> Result<Vec<KeywordRecord>> {
+        let mut rows = self.parse_csv(body)?.into_iter();
+        let header = rows.next().ok_or_else(|| anyhow!("CSV 文件为空"))?;
+        let column = |name: &str| header.iter().position(|field| field.trim() == name);
+        let (Some(keywords_col), Some(reply_col)) = (column("keywords"), column("reply")) else {
+            return Err(anyhow!("CSV 表头必须包含 keywords 和 reply 列"));
+        };
+        let aliases_col = column("aliases");
+        let exclude_col = column("exclude_words");
+        let require_col = column("require_words");
+        let cooldown_col = column("cooldown_secs");
+        let action_col = column("action");
+        let action_duration_col = column("action_duration_secs");
+
+        let field = |row: &[String], col: Option<usize>| {
+            col.and_then(|col| row.get(col))
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        };
+        let optional = |value: String| if value.is_empty() { None } else { Some(value) };
+
+        let records = rows
+            .filter(|row| row.iter().any(|field| !field.is_empty()))
+            .map(|row| KeywordRecord {
+                keywords: field(&row, Some(keywords_col)),
+                reply: field(&row, Some(reply_col)),
+                aliases: field(&row, aliases_col)
+                    .split('|')
+                    .filter(|alias| !alias.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                exclude_words: optional(field(&row, exclude_col)),
+                require_words: optional(field(&row, require_col)),
+                cooldown_secs: field(&row, cooldown_col).trim().parse().ok(),
+                action: optional(field(&row, action_col)),
+                action_duration_secs: field(&row, action_duration_col).trim().parse().ok(),
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    /// 解析 RFC 4180 格式的 CSV，支持引号包裹的逗号、换行和转义引号
+    fn parse_csv(&self, body: &str) -> Result<Vec<Vec<String>>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = body.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    _ => field.push(c),
+                }
+                continue;
+            }
+
+            match c {
+                '"' if field.is_empty() => in_quotes = true,
+                ',' => row.push(std::mem::take(&mut field)),
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' | '\r' => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                _ => field.push(c),
+            }
+        }
+
+        if in_quotes {
+            return Err(anyhow!("CSV 中存在未闭合的引号"));
+        }
+        if !field.is_empty() || !row.is_empty() {
+            row.push(field);
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeywordRecord;
+    use crate::handlers::test_handler;
+
+    fn record(keywords: &str, reply: &str) -> KeywordRecord {
+        KeywordRecord {
+            keywords: keywords.to_string(),
+            reply: reply.to_string(),
+            aliases: Vec::new(),
+            exclude_words: None,
+            require_words: None,
+            cooldown_secs: None,
+            action: None,
+            action_duration_secs: None,
+        }
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_all_fields() {
+        let handler = test_handler();
+        let records = vec![
+            KeywordRecord {
+                aliases: vec!["规则".to_string(), "rules".to_string()],
+                exclude_words: Some("不看|别发".to_string()),
+                require_words: Some("群".to_string()),
+                cooldown_secs: Some(60),
+                action: Some("mute".to_string()),
+                action_duration_secs: Some(600),
+                ..record("群规", "第一行, 带逗号\n第二行 \"引号\"")
+            },
+            record("你好", "<b>欢迎</b>"),
+        ];
+
+        let csv = handler.records_to_csv(&records);
+        assert_eq!(handler.csv_to_records(&csv).unwrap(), records);
+    }
+
+    #[test]
+    fn parse_csv_handles_quotes_and_line_endings() {
+        let handler = test_handler();
+        let rows = handler
+            .parse_csv("a,\"b,c\"\r\n\"d\"\"e\",\"f\ng\"\nh,")
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_string(), "b,c".to_string()],
+                vec!["d\"e".to_string(), "f\ng".to_string()],
+                vec!["h".to_string(), String::new()],
+            ]
+        );
+        assert!(handler.parse_csv("a,\"b").is_err());
+    }
+
+    #[test]
+    fn csv_to_records_requires_header_columns() {
+        let handler = test_handler();
+        assert!(handler.csv_to_records("").is_err());
+        assert!(
+            handler
+                .csv_to_records("keywords,aliases\r\n群规,规则")
+                .is_err()
+        );
+
+        let records = handler
+            .csv_to_records("reply,keywords\r\n回复,群规\r\n,\r\n")
+            .unwrap();
+        assert_eq!(records, vec![record("群规", "回复")]);
+    }
+
+    #[test]
+    fn parse_records_accepts_json_export_and_array() {
+        let handler = test_handler();
+        let export = "\u{feff}{\"group_id\": 1, \"keywords\": [{\"keywords\": \"群规\", \"reply\": \"回复\"}]}";
+        assert_eq!(
+            handler.parse_records(export).unwrap(),
+            vec![record("群规", "回复")]
+        );
+
+        let array = "[{\"keywords\": \"群规\", \"reply\": \"回复\"}]";
+        assert_eq!(
+            handler.parse_records(array).unwrap(),
+            vec![record("群规", "回复")]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use super::MessageHandler;
+use super::audit::{AuditAction, AuditEntry, Operation};
+use super::keyword_list::{ListKind, ListSort};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::entities::keyword_alias::{self, Entity as KeywordAliasEntity};
+use crate::entities::keyword_trash::{self, Entity as KeywordTrashEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// 从回收站恢复关键词的结果
+pub(super) enum RestoreResult {
+    /// 已恢复，附带因已被占用而未能恢复的别名
+    Restored(Vec<String>),
+    /// 同名关键词或别名已存在
+    Conflict,
+}
+
+impl MessageHandler {
+    /// 将关键词连同别名和触发条件移入回收站，原记录由调用方随后删除
+    pub(super) async fn move_to_trash<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        operation: Operation,
+        reply: &group_reply::Model,
+    ) -> Result<()> {
+        let aliases: Vec<String> = KeywordAliasEntity::find()
+            .filter(keyword_alias::Column::GroupId.eq(reply.group_id))
+            .filter(keyword_alias::Column::Keywords.eq(&reply.keywords))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|alias| alias.alias)
+            .collect();
+
+        let trash = keyword_trash::ActiveModel {
+            group_id: Set(reply.group_id),
+            keywords: Set(reply.keywords.clone()),
+            reply: Set(reply.reply.clone()),
+            exclude_words: Set(reply.exclude_words.clone()),
+            require_words: Set(reply.require_words.clone()),
+            cooldown_secs: Set(reply.cooldown_secs),
+            action: Set(reply.action.clone()),
+            action_duration_secs: Set(reply.action_duration_secs),
+            aliases: Set((!aliases.is_empty()).then(|| aliases.join("|"))),
+            operation_id: Set(operation.id),
+            deleted_by: Set(operation.actor_id),
+            deleted_at: Set(chrono::Utc::now().timestamp()),
+            ..Default::default()
+        };
+        trash.insert(db).await?;
+
+        Ok(())
+    }
+
+    /// 恢复回收站中的一条记录，恢复后从回收站移除
+    pub(super) async fn restore_from_trash<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        operation: Operation,
+        trash: keyword_trash::Model,
+    ) -> Result<RestoreResult> {
+        let keyword_conflict = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(trash.group_id))
+            .filter(group_reply::Column::Keywords.eq(&trash.keywords))
+            .one(db)
+            .await?;
+        let alias_conflict = KeywordAliasEntity::find()
+            .filter(keyword_alias::Column::GroupId.eq(trash.group_id))
+            .filter(keyword_alias::Column::Alias.eq(&trash.keywords))
+            .one(db)
+            .await?;
+        if keyword_conflict.is_some() || alias_conflict.is_some() {
+            return Ok(RestoreResult::Conflict);
+        }
+
+        self.record_audit(
+            db,
+            AuditEntry {
+                group_id: trash.group_id,
+                operation,
+                action: AuditAction::Restore,
+                keywords: &trash.keywords,
+                old_keywords: None,
+                old_reply: None,
+                new_reply: Some(trash.reply.clone()),
+            },
+        )
+        .await?;
+
+        let reply = group_reply::ActiveModel {
+            group_id: Set(trash.group_id),
+            keywords: Set(trash.keywords.clone()),
+            reply: Set(trash.reply.clone()),
+            exclude_words: Set(trash.exclude_words.clone()),
+            require_words: Set(trash.require_words.clone()),
+            cooldown_secs: Set(trash.cooldown_secs),
+            action: Set(trash.action.clone()),
+            action_duration_secs: Set(trash.action_duration_secs),
+        };
+        reply.insert(db).await?;
+
+        // 删除期间已被其他关键词占用的别名不再抢回
+        let mut skipped = Vec::new();
+        let mut aliases = Vec::new();
+        for alias in self.split_triggers(trash.aliases.as_deref().unwrap_or_default()) {
+            let taken = KeywordAliasEntity::find()
+                .filter(keyword_alias::Column::GroupId.eq(trash.group_id))
+                .filter(keyword_alias::Column::Alias.eq(&alias))
+                .one(db)
+                .await?
+                .is_some();
+            if taken {
+                skipped.push(alias);
+            } else {
+                aliases.push(alias);
+            }
+        }
+        skipped.extend(
+            self.add_keyword_aliases(db, trash.group_id, &trash.keywords, &aliases)
+                .await?,
+        );
+
+        trash.delete(db).await?;
+        Ok(RestoreResult::Restored(skipped))
+    }
+
+    pub(super) async fn handle_trash_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        match self
+            .render_list_page(group_id, ListKind::Trash, ListSort::Default, 0, "")
+            .await?
+        {
+            Some(page) => {
+                self.send_reply_with_markup(api, message.chat.id, &page.text, None, page.keyboard)
+                    .await?;
+            }
+            None => {
+                self.send_reply(api, message.chat.id, "回收站是空的", None)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 按删除时间倒序生成回收站的列表行
+    pub(super) async fn trash_list_lines(&self, group_id: i64) -> Result<Vec<String>> {
+        let lines = KeywordTrashEntity::find()
+            .filter(keyword_trash::Column::GroupId.eq(group_id))
+            .order_by_desc(keyword_trash::Column::Id)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|trash| {
+                let mut line = format!(
+                    "{} <code>{}</code> 删除 <code>{}</code>",
+                    self.format_timestamp(trash.deleted_at),
+                    trash.deleted_by,
+                    self.escape_html(&trash.keywords)
+                );
+                for alias in self.split_triggers(trash.aliases.as_deref().unwrap_or_default()) {
+                    line.push_str(&format!(" | <code>{}</code>", self.escape_html(&alias)));
+                }
+                line
+            })
+            .collect();
+
+        Ok(lines)
+    }
+
+    pub(super) async fn handle_restore_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let keywords = content.trim();
+        if keywords.is_empty() {
+            self.send_reply(api, message.chat.id, "用法: /restore &lt;关键词&gt;", None)
+                .await?;
+            return Ok(());
+        }
+
+        // 同一关键词可能被删除多次，恢复最近删除的一条，也可以用别名查找
+        let trash = KeywordTrashEntity::find()
+            .filter(keyword_trash::Column::GroupId.eq(group_id))
+            .order_by_desc(keyword_trash::Column::Id)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .find(|trash| {
+                trash.keywords == keywords
+                    || trash
+                        .aliases
+                        .as_deref()
+                        .is_some_and(|aliases| aliases.split('|').any(|alias| alias == keywords))
+            });
+        let Some(trash) = trash else {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!(
+                    "回收站中没有关键词 <code>{}</code>",
+                    self.escape_html(keywords)
+                ),
+                None,
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let restored_keywords = self.escape_html(&trash.keywords);
+        let txn = self.db.begin().await?;
+        let result = self
+            .restore_from_trash(&txn, self.begin_operation(message), trash)
+            .await?;
+        txn.commit().await?;
+
+        let message_text = match result {
+            RestoreResult::Restored(skipped) => {
+                let mut text = format!("关键词 <code>{}</code> 已恢复！", restored_keywords);
+                if !skipped.is_empty() {
+                    let skipped: Vec<String> = skipped
+                        .iter()
+                        .map(|alias| format!("<code>{}</code>", self.escape_html(alias)))
+                        .collect();
+                    text.push_str(&format!(
+                        "\n以下别名已被占用，未能恢复: {}",
+                        skipped.join(", ")
+                    ));
+                }
+                text
+            }
+            RestoreResult::Conflict => format!(
+                "关键词或别名 <code>{}</code> 已存在，请先删除或重命名后再恢复",
+                restored_keywords
+            ),
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 清除回收站中超过保留期限的关键词，返回清除的条数
+    pub async fn purge_expired_trash(&self) -> Result<u64> {
+        let retention = i64::try_from(self.config.trash_retention_days)
+            .unwrap_or(i64::MAX)
+            .saturating_mul(SECS_PER_DAY);
+        let cutoff = chrono::Utc::now().timestamp().saturating_sub(retention);
+
+        let result = KeywordTrashEntity::delete_many()
+            .filter(keyword_trash::Column::DeletedAt.lt(cutoff))
+            .exec(&self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
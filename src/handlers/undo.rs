@@ -0,0 +1,157 @@
+use super::audit::{AuditAction, Operation};
+use super::trash::RestoreResult;
+use super::{MessageHandler, RenameResult};
+use crate::entities::group_reply::{self, Entity as GroupReplyEntity};
+use crate::entities::keyword_audit::{self, Entity as KeywordAuditEntity};
+use crate::entities::keyword_trash::{self, Entity as KeywordTrashEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::Message;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+
+impl MessageHandler {
+    /// 撤销调用者在当前群组中最近一次仍可撤销的操作
+    pub(super) async fn handle_undo_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let operation = self.begin_operation(message);
+        let latest = KeywordAuditEntity::find()
+            .filter(keyword_audit::Column::GroupId.eq(group_id))
+            .filter(keyword_audit::Column::ActorId.eq(operation.actor_id))
+            .filter(keyword_audit::Column::Undoable.eq(true))
+            .order_by_desc(keyword_audit::Column::Id)
+            .one(&self.db)
+            .await?;
+        let Some(latest) = latest else {
+            self.send_reply(api, message.chat.id, "你在本群没有可以撤销的操作", None)
+                .await?;
+            return Ok(());
+        };
+
+        // 同一操作的记录按相反的顺序逐条撤销
+        let entries = KeywordAuditEntity::find()
+            .filter(keyword_audit::Column::GroupId.eq(group_id))
+            .filter(keyword_audit::Column::OperationId.eq(latest.operation_id))
+            .order_by_desc(keyword_audit::Column::Id)
+            .all(&self.db)
+            .await?;
+
+        let txn = self.db.begin().await?;
+        let mut reverted = 0;
+        let mut skipped = Vec::new();
+        for entry in &entries {
+            if self
+                .undo_audit_entry(&txn, group_id, operation, entry)
+                .await?
+            {
+                reverted += 1;
+            } else {
+                skipped.push(entry.keywords.clone());
+            }
+        }
+
+        // 被撤销的操作和撤销本身产生的记录都不能再撤销
+        KeywordAuditEntity::update_many()
+            .col_expr(keyword_audit::Column::Undoable, Expr::value(false))
+            .filter(keyword_audit::Column::GroupId.eq(group_id))
+            .filter(keyword_audit::Column::OperationId.is_in([latest.operation_id, operation.id]))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        let mut message_text = format!(
+            "已撤销你在 {} 的{}操作，共撤销 {} 项",
+            self.format_timestamp(latest.created_at),
+            AuditAction::label(&latest.action),
+            reverted
+        );
+        if !skipped.is_empty() {
+            let skipped: Vec<String> = skipped
+                .iter()
+                .map(|keywords| format!("<code>{}</code>", self.escape_html(keywords)))
+                .collect();
+            message_text.push_str(&format!(
+                "\n以下关键词已被修改或占用，未能撤销: {}",
+                skipped.join(", ")
+            ));
+        }
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 撤销一条变更记录，撤销产生的修改同样写入变更记录，无法撤销时返回 false
+    async fn undo_audit_entry<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        group_id: i64,
+        operation: Operation,
+        entry: &keyword_audit::Model,
+    ) -> Result<bool> {
+        let Ok(action) = entry.action.parse::<AuditAction>() else {
+            return Ok(false);
+        };
+
+        // 撤销前确认关键词仍是这次操作写入的内容，其他人之后的修改不能被覆盖
+        let current = GroupReplyEntity::find()
+            .filter(group_reply::Column::GroupId.eq(group_id))
+            .filter(group_reply::Column::Keywords.eq(&entry.keywords))
+            .one(db)
+            .await?;
+        let unchanged = current.is_some_and(|current| Some(current.reply) == entry.new_reply);
+
+        match action {
+            // 撤销添加时移入回收站，不会丢失撤销前的内容
+            AuditAction::Add | AuditAction::Restore => {
+                if !unchanged {
+                    return Ok(false);
+                }
+                self.delete_keyword_reply(db, group_id, operation, entry.keywords.clone())
+                    .await
+            }
+            AuditAction::Update => {
+                let Some(old_reply) = entry.old_reply.clone().filter(|_| unchanged) else {
+                    return Ok(false);
+                };
+                self.add_keyword_reply(db, group_id, operation, entry.keywords.clone(), old_reply)
+                    .await?;
+                Ok(true)
+            }
+            AuditAction::Delete | AuditAction::DeleteAll => {
+                let trash = KeywordTrashEntity::find()
+                    .filter(keyword_trash::Column::GroupId.eq(group_id))
+                    .filter(keyword_trash::Column::OperationId.eq(entry.operation_id))
+                    .filter(keyword_trash::Column::Keywords.eq(&entry.keywords))
+                    .one(db)
+                    .await?;
+                let Some(trash) = trash else {
+                    return Ok(false);
+                };
+                let result = self.restore_from_trash(db, operation, trash).await?;
+                Ok(matches!(result, RestoreResult::Restored(_)))
+            }
+            AuditAction::Rename => {
+                let Some(old_keywords) = entry.old_keywords.clone() else {
+                    return Ok(false);
+                };
+                let result = self
+                    .rename_keyword_reply(
+                        db,
+                        group_id,
+                        operation,
+                        entry.keywords.clone(),
+                        old_keywords,
+                    )
+                    .await?;
+                Ok(matches!(result, RenameResult::Renamed))
+            }
+        }
+    }
+}
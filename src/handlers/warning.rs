@@ -0,0 +1,541 @@
+use super::MessageHandler;
+use super::moderation::KeywordAction;
+use super::permission::TargetUser;
+use crate::config::AdminRight;
+use crate::entities::moderation_log::{self, Entity as ModerationLogEntity};
+use crate::entities::user_warning::{self, Entity as UserWarningEntity};
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::{Message, MessageEntityType};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use std::collections::BTreeMap;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+// /modlog 显示的最近记录条数
+const MOD_LOG_SHOWN: u64 = 20;
+
+// 警告阈值的上限
+const MAX_WARN_THRESHOLD: u32 = 100;
+
+impl MessageHandler {
+    /// 仍然有效的警告的最早时间，未开启过期时为 None
+    fn warning_cutoff(&self) -> Option<i64> {
+        let expire = i64::try_from(self.config.warn_expire_days).unwrap_or(i64::MAX);
+        (expire > 0).then(|| {
+            chrono::Utc::now()
+                .timestamp()
+                .saturating_sub(expire.saturating_mul(SECS_PER_DAY))
+        })
+    }
+
+    /// 查询用户在群组中仍然有效的警告，按时间先后排列
+    async fn active_warnings(
+        &self,
+        group_id: i64,
+        user_id: i64,
+    ) -> Result<Vec<user_warning::Model>> {
+        let mut query = UserWarningEntity::find()
+            .filter(user_warning::Column::GroupId.eq(group_id))
+            .filter(user_warning::Column::UserId.eq(user_id));
+        if let Some(cutoff) = self.warning_cutoff() {
+            query = query.filter(user_warning::Column::CreatedAt.gt(cutoff));
+        }
+
+        Ok(query
+            .order_by_asc(user_warning::Column::Id)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// 记录一次警告，有效警告达到群组设置的阈值时自动禁言或封禁，返回展示给群组的说明
+    pub(super) async fn issue_warning(
+        &self,
+        api: &Bot,
+        group_id: i64,
+        target: TargetUser,
+        user_label: &str,
+        reason: Option<String>,
+        actor_id: i64,
+    ) -> Result<String> {
+        let TargetUser { user_id, username } = target;
+        let warning = user_warning::ActiveModel {
+            group_id: Set(group_id),
+            user_id: Set(user_id as i64),
+            username: Set(username),
+            reason: Set(reason.clone()),
+            warned_by: Set(actor_id),
+            created_at: Set(chrono::Utc::now().timestamp()),
+            ..Default::default()
+        };
+        warning.insert(&self.db).await?;
+        self.log_moderation(
+            group_id,
+            user_id as i64,
+            "warn",
+            reason.as_deref(),
+            actor_id,
+        )
+        .await?;
+
+        let count = self.active_warnings(group_id, user_id as i64).await?.len();
+        let setting = self.load_group_setting(group_id).await?;
+        let reached = |threshold: i32| threshold > 0 && count >= threshold as usize;
+
+        let mut text = format!("{} 收到警告（第 {} 次）", user_label, count);
+        if let Some(reason) = &reason {
+            text.push_str(&format!("，原因: {}", self.escape_html(reason)));
+        }
+
+        // 封禁优先于禁言，两者都达到时只封禁
+        let (action, duration_secs) = if reached(setting.warn_ban_threshold) {
+            (KeywordAction::Ban, None)
+        } else if reached(setting.warn_mute_threshold) {
+            (KeywordAction::Mute, Some(setting.warn_mute_secs))
+        } else {
+            return Ok(text);
+        };
+
+        if !self
+            .bot_has_right(api, group_id, AdminRight::RestrictMembers)
+            .await?
+        {
+            text.push_str(&format!(
+                "\n警告已达到{}阈值，但机器人需要具备 <code>{}</code> 管理员权限才能执行",
+                action.label(),
+                AdminRight::RestrictMembers.as_str()
+            ));
+            return Ok(text);
+        }
+        if !self
+            .restrict_member(api, group_id, user_id, action, duration_secs)
+            .await?
+        {
+            text.push_str(&format!("\n自动{}失败，请手动处理", action.label()));
+            return Ok(text);
+        }
+
+        let escalation_reason = format!("警告达到 {} 次", count);
+        self.log_moderation(
+            group_id,
+            user_id as i64,
+            action.as_str(),
+            Some(&escalation_reason),
+            0,
+        )
+        .await?;
+        text.push_str(&format!(
+            "\n警告已达到 {} 次，已被{}{}",
+            count,
+            action.label(),
+            self.format_action_duration(duration_secs)
+        ));
+
+        // 封禁后清空警告，解封后重新计数
+        if action == KeywordAction::Ban {
+            UserWarningEntity::delete_many()
+                .filter(user_warning::Column::GroupId.eq(group_id))
+                .filter(user_warning::Column::UserId.eq(user_id as i64))
+                .exec(&self.db)
+                .await?;
+        }
+
+        Ok(text)
+    }
+
+    /// 拆分目标用户和原因，文字提及之后的内容都是原因
+    ///
+    /// 第一个词是 `@用户名` 或用户 ID 时作为目标，否则回复了用户的消息时全部内容都是原因
+    fn split_target_and_reason(
+        &self,
+        message: &Message,
+        has_reply: bool,
+    ) -> Result<(String, Option<String>)> {
+        let text = message.text.as_deref().unwrap_or("");
+        let mention_end = message.entities.iter().flatten().find_map(|entity| {
+            (entity.type_field == MessageEntityType::TextMention)
+                .then(|| entity.offset as usize + entity.length as usize)
+        });
+
+        let rest = match mention_end {
+            Some(end) => &text[self.utf16_to_utf8_offset(text, end)?..],
+            None => text
+                .split_once(char::is_whitespace)
+                .map_or("", |(_, rest)| rest),
+        };
+        let rest = rest.trim();
+        let (first, remainder) = rest
+            .split_once(char::is_whitespace)
+            .map_or((rest, ""), |(first, remainder)| (first, remainder.trim()));
+        let explicit = first.starts_with('@') || first.parse::<u64>().is_ok();
+        let (target, reason) = if mention_end.is_some() || (has_reply && !explicit) {
+            ("", rest)
+        } else {
+            (first, remainder)
+        };
+
+        Ok((
+            target.to_string(),
+            (!reason.is_empty()).then(|| reason.to_string()),
+        ))
+    }
+
+    pub(super) async fn handle_warn_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let has_reply = self.replied_user(&api, message).await?.is_some();
+        let (target, reason) = self.split_target_and_reason(message, has_reply)?;
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, &target)
+            .await?
+        else {
+            return Ok(());
+        };
+        if self
+            .find_chat_admin(&api, group_id, target.user_id)
+            .await?
+            .is_some()
+        {
+            self.send_reply(api, message.chat.id, "不能警告群组管理员", None)
+                .await?;
+            return Ok(());
+        }
+
+        let user_label = self.format_user(target.user_id as i64, target.username.as_deref());
+        let text = self
+            .issue_warning(
+                &api,
+                group_id,
+                target,
+                &user_label,
+                reason,
+                self.actor_id(message),
+            )
+            .await?;
+        self.send_reply(api, message.chat.id, &text, None).await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_warns_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        let target = content.trim();
+
+        // 不带参数且没有回复或提及用户时列出所有仍有警告的用户
+        let has_mention = message
+            .entities
+            .iter()
+            .flatten()
+            .any(|entity| entity.type_field == MessageEntityType::TextMention);
+        if target.is_empty() && !has_mention && self.replied_user(&api, message).await?.is_none() {
+            return self.show_warned_users(api, message, group_id).await;
+        }
+
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, target)
+            .await?
+        else {
+            return Ok(());
+        };
+        let user = self.format_user(target.user_id as i64, target.username.as_deref());
+        let warnings = self
+            .active_warnings(group_id, target.user_id as i64)
+            .await?;
+        if warnings.is_empty() {
+            self.send_reply(
+                api,
+                message.chat.id,
+                &format!("{} 没有有效的警告", user),
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut text = format!("<b>{} 的警告（{} 次）:</b>\n", user, warnings.len());
+        for (index, warning) in warnings.iter().enumerate() {
+            let by = if warning.warned_by == 0 {
+                "关键词".to_string()
+            } else {
+                format!("<code>{}</code>", warning.warned_by)
+            };
+            text.push_str(&format!(
+                "{}. {} 由 {} 发出",
+                index + 1,
+                self.format_timestamp(warning.created_at),
+                by
+            ));
+            if let Some(reason) = &warning.reason {
+                text.push_str(&format!("，原因: {}", self.escape_html(reason)));
+            }
+            text.push('\n');
+        }
+        self.send_reply(api, message.chat.id, text.trim_end(), None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn show_warned_users(&self, api: Bot, message: &Message, group_id: i64) -> Result<()> {
+        let mut query =
+            UserWarningEntity::find().filter(user_warning::Column::GroupId.eq(group_id));
+        if let Some(cutoff) = self.warning_cutoff() {
+            query = query.filter(user_warning::Column::CreatedAt.gt(cutoff));
+        }
+
+        // 按用户汇总，用户名取最近一次警告时记录的
+        let mut users: BTreeMap<i64, (usize, Option<String>)> = BTreeMap::new();
+        for warning in query
+            .order_by_asc(user_warning::Column::Id)
+            .all(&self.db)
+            .await?
+        {
+            let entry = users.entry(warning.user_id).or_default();
+            entry.0 += 1;
+            if warning.username.is_some() {
+                entry.1 = warning.username;
+            }
+        }
+        if users.is_empty() {
+            self.send_reply(api, message.chat.id, "当前群组没有有效的警告", None)
+                .await?;
+            return Ok(());
+        }
+
+        let mut users: Vec<_> = users.into_iter().collect();
+        users.sort_by_key(|(_, (count, _))| std::cmp::Reverse(*count));
+        let mut text = "<b>有效警告:</b>\n".to_string();
+        for (user_id, (count, username)) in users {
+            text.push_str(&format!(
+                "{} - {} 次\n",
+                self.format_user(user_id, username.as_deref()),
+                count
+            ));
+        }
+        self.send_reply(api, message.chat.id, text.trim_end(), None)
+            .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_resetwarns_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let has_reply = self.replied_user(&api, message).await?.is_some();
+        let (target, reason) = self.split_target_and_reason(message, has_reply)?;
+        let Some(target) = self
+            .resolve_target_user(&api, message, group_id, &target)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let result = UserWarningEntity::delete_many()
+            .filter(user_warning::Column::GroupId.eq(group_id))
+            .filter(user_warning::Column::UserId.eq(target.user_id as i64))
+            .exec(&self.db)
+            .await?;
+        let user = self.format_user(target.user_id as i64, target.username.as_deref());
+        let message_text = if result.rows_affected > 0 {
+            self.log_moderation(
+                group_id,
+                target.user_id as i64,
+                "reset_warns",
+                reason.as_deref(),
+                self.actor_id(message),
+            )
+            .await?;
+            format!("已清除 {} 的 {} 次警告", user, result.rows_affected)
+        } else {
+            format!("{} 没有警告", user)
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_warnconfig_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+    ) -> Result<()> {
+        let usage = "用法: /warnconfig mute &lt;次数&gt; [时长] 或 /warnconfig ban &lt;次数&gt;，次数为 0 表示不自动处理";
+        let content = self.get_content_after_command(message, command)?;
+        let args: Vec<&str> = content.split_whitespace().collect();
+        let parse_threshold = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|threshold| *threshold <= MAX_WARN_THRESHOLD)
+                .map(|threshold| threshold as i32)
+        };
+
+        let message_text = match args.as_slice() {
+            [] => {
+                let setting = self.load_group_setting(group_id).await?;
+                let describe = |threshold: i32| {
+                    if threshold > 0 {
+                        format!("{} 次", threshold)
+                    } else {
+                        "不自动处理".to_string()
+                    }
+                };
+                let expire = if self.config.warn_expire_days > 0 {
+                    format!("{} 天", self.config.warn_expire_days)
+                } else {
+                    "永不过期".to_string()
+                };
+                format!(
+                    "禁言阈值: {}（禁言 {}）\n封禁阈值: {}\n警告有效期: {}\n{}",
+                    describe(setting.warn_mute_threshold),
+                    self.format_duration(setting.warn_mute_secs.max(0) as u32),
+                    describe(setting.warn_ban_threshold),
+                    expire,
+                    usage
+                )
+            }
+            ["mute", threshold, rest @ ..] if rest.len() <= 1 => {
+                let Some(threshold) = parse_threshold(threshold) else {
+                    self.send_reply(api, message.chat.id, usage, None).await?;
+                    return Ok(());
+                };
+                let duration = match rest.first() {
+                    Some(duration) => match self
+                        .parse_duration(duration)
+                        .and_then(|secs| i32::try_from(secs).ok())
+                        .filter(|secs| *secs >= 30)
+                    {
+                        Some(secs) => Some(secs),
+                        None => {
+                            self.send_reply(api, message.chat.id, "禁言时长至少为 30 秒", None)
+                                .await?;
+                            return Ok(());
+                        }
+                    },
+                    None => None,
+                };
+                self.update_group_setting(group_id, |setting| {
+                    setting.warn_mute_threshold = Set(threshold);
+                    if let Some(secs) = duration {
+                        setting.warn_mute_secs = Set(secs);
+                    }
+                })
+                .await?;
+                if threshold > 0 {
+                    format!("有效警告达到 {} 次时将自动禁言", threshold)
+                } else {
+                    "已关闭警告自动禁言".to_string()
+                }
+            }
+            ["ban", threshold] => {
+                let Some(threshold) = parse_threshold(threshold) else {
+                    self.send_reply(api, message.chat.id, usage, None).await?;
+                    return Ok(());
+                };
+                self.update_group_setting(group_id, |setting| {
+                    setting.warn_ban_threshold = Set(threshold);
+                })
+                .await?;
+                if threshold > 0 {
+                    format!("有效警告达到 {} 次时将自动封禁", threshold)
+                } else {
+                    "已关闭警告自动封禁".to_string()
+                }
+            }
+            _ => usage.to_string(),
+        };
+        self.send_reply(api, message.chat.id, &message_text, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 显示最近的管理操作记录
+    pub(super) async fn handle_modlog_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        group_id: i64,
+    ) -> Result<()> {
+        let logs = ModerationLogEntity::find()
+            .filter(moderation_log::Column::GroupId.eq(group_id))
+            .order_by_desc(moderation_log::Column::Id)
+            .limit(MOD_LOG_SHOWN)
+            .all(&self.db)
+            .await?;
+        if logs.is_empty() {
+            self.send_reply(api, message.chat.id, "当前群组没有管理操作记录", None)
+                .await?;
+            return Ok(());
+        }
+
+        let total = ModerationLogEntity::find()
+            .filter(moderation_log::Column::GroupId.eq(group_id))
+            .count(&self.db)
+            .await?;
+        let mut text = format!("<b>最近的管理操作（共 {} 条）:</b>\n", total);
+        for log in logs {
+            let actor = if log.actor_id == 0 {
+                "自动".to_string()
+            } else {
+                format!("<code>{}</code>", log.actor_id)
+            };
+            text.push_str(&format!(
+                "{} {} 对 <code>{}</code> {}",
+                self.format_timestamp(log.created_at),
+                actor,
+                log.user_id,
+                self.moderation_action_label(&log.action)
+            ));
+            if let Some(reason) = &log.reason {
+                text.push_str(&format!("，{}", self.escape_html(reason)));
+            }
+            text.push('\n');
+        }
+        self.send_reply(api, message.chat.id, text.trim_end(), None)
+            .await?;
+
+        Ok(())
+    }
+
+    fn moderation_action_label<'a>(&self, action: &'a str) -> &'a str {
+        match action {
+            "reset_warns" => "清除警告",
+            action => action
+                .parse::<KeywordAction>()
+                .map_or(action, |action| action.label()),
+        }
+    }
+
+    /// 清除已过期的警告，返回清除的条数
+    pub async fn purge_expired_warnings(&self) -> Result<u64> {
+        let Some(cutoff) = self.warning_cutoff() else {
+            return Ok(0);
+        };
+
+        let result = UserWarningEntity::delete_many()
+            .filter(user_warning::Column::CreatedAt.lte(cutoff))
+            .exec(&self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,217 @@
+use super::MessageHandler;
+use super::placeholder::PLACEHOLDER_HELP;
+use crate::entities::group_setting;
+use anyhow::Result;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::types::{Message, User};
+use sea_orm::Set;
+
+/// 成员加入或离开群组时发送的消息
+#[derive(Clone, Copy)]
+pub(super) enum Greeting {
+    Welcome,
+    Goodbye,
+}
+
+impl Greeting {
+    fn label(self) -> &'static str {
+        match self {
+            Greeting::Welcome => "欢迎消息",
+            Greeting::Goodbye => "告别消息",
+        }
+    }
+
+    fn set_command(self) -> &'static str {
+        match self {
+            Greeting::Welcome => "/setwelcome",
+            Greeting::Goodbye => "/setgoodbye",
+        }
+    }
+
+    fn toggle_command(self) -> &'static str {
+        match self {
+            Greeting::Welcome => "/welcome",
+            Greeting::Goodbye => "/goodbye",
+        }
+    }
+
+    fn template(self, setting: &group_setting::Model) -> Option<&str> {
+        match self {
+            Greeting::Welcome => setting.welcome_message.as_deref(),
+            Greeting::Goodbye => setting.goodbye_message.as_deref(),
+        }
+    }
+
+    fn set_template(self, setting: &mut group_setting::ActiveModel, template: Option<String>) {
+        match self {
+            Greeting::Welcome => setting.welcome_message = Set(template),
+            Greeting::Goodbye => setting.goodbye_message = Set(template),
+        }
+    }
+}
+
+impl MessageHandler {
+    /// 处理成员加入和离开的服务消息，按群组设置的模板发送欢迎或告别消息
+    pub(super) async fn handle_member_service_message(
+        &self,
+        api: Bot,
+        message: &Message,
+    ) -> Result<()> {
+        let setting = self.load_group_setting(message.chat.id).await?;
+
+        if let Some(members) = &message.new_chat_members {
+            // 机器人加入时不验证也不发送欢迎消息，包括本机器人被拉进群组
+            for member in members.iter().filter(|member| !member.is_bot) {
+                // 开启验证时欢迎消息在通过验证后才发送
+                if setting.captcha_enabled
+                    && self.start_captcha(&api, message, &setting, member).await?
+                {
+                    continue;
+                }
+                if let Some(template) = Greeting::Welcome.template(&setting) {
+                    self.send_greeting(api.clone(), message, template, member)
+                        .await?;
+                }
+            }
+        }
+
+        if let Some(member) = message.left_chat_member.as_deref()
+            && !member.is_bot
+        {
+            // 未完成验证就离开的成员不发送告别消息，被机器人移出的成员同样不发送
+            let pending = self
+                .cancel_captcha(&api, message.chat.id, member.id)
+                .await?;
+            let removed_by_bot = message.from.as_ref().is_some_and(|from| from.is_bot);
+            if !pending
+                && !removed_by_bot
+                && let Some(template) = Greeting::Goodbye.template(&setting)
+            {
+                self.send_greeting(api, message, template, member).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_greeting(
+        &self,
+        api: Bot,
+        message: &Message,
+        template: &str,
+        member: &User,
+    ) -> Result<()> {
+        let text = self.render_placeholders(template, message, Some(member));
+        self.send_reply(api, message.chat.id, &text, None).await
+    }
+
+    pub(super) async fn handle_set_greeting_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+        greeting: Greeting,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        if content.trim().is_empty() {
+            let setting = self.load_group_setting(group_id).await?;
+            self.send_greeting_status(api, message, &setting, greeting)
+                .await?;
+            return Ok(());
+        }
+
+        // 与关键词回复相同，保留 HTML 并将 code 实体转换为 <code> 标签
+        let template = self.process_reply_with_entities(&content, message).await?;
+        self.update_group_setting(group_id, |setting| {
+            greeting.set_template(setting, Some(template));
+        })
+        .await?;
+
+        self.send_reply(
+            api,
+            message.chat.id,
+            &format!(
+                "{}已设置，使用 {} off 关闭",
+                greeting.label(),
+                greeting.toggle_command()
+            ),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn handle_greeting_command(
+        &self,
+        api: Bot,
+        message: &Message,
+        command: &str,
+        group_id: i64,
+        greeting: Greeting,
+    ) -> Result<()> {
+        let content = self.get_content_after_command(message, command)?;
+        match content.trim() {
+            "" => {
+                let setting = self.load_group_setting(group_id).await?;
+                self.send_greeting_status(api, message, &setting, greeting)
+                    .await?;
+            }
+            "off" => {
+                self.update_group_setting(group_id, |setting| {
+                    greeting.set_template(setting, None);
+                })
+                .await?;
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!("{}已关闭", greeting.label()),
+                    None,
+                )
+                .await?;
+            }
+            _ => {
+                self.send_reply(
+                    api,
+                    message.chat.id,
+                    &format!(
+                        "用法: {} off 关闭{}，使用 {} &lt;内容&gt; 设置",
+                        greeting.toggle_command(),
+                        greeting.label(),
+                        greeting.set_command()
+                    ),
+                    None,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_greeting_status(
+        &self,
+        api: Bot,
+        message: &Message,
+        setting: &group_setting::Model,
+        greeting: Greeting,
+    ) -> Result<()> {
+        let status = match greeting.template(setting) {
+            Some(template) => format!(
+                "<b>当前{}:</b>\n<pre>{}</pre>",
+                greeting.label(),
+                self.escape_html(template)
+            ),
+            None => format!("本群未设置{}", greeting.label()),
+        };
+        let text = format!(
+            "{}\n\n用法: {} &lt;内容&gt;，{} off 关闭\n可用占位符: {}",
+            status,
+            greeting.set_command(),
+            greeting.toggle_command(),
+            PLACEHOLDER_HELP
+        );
+        self.send_reply(api, message.chat.id, &text, None).await
+    }
+}
@@ -3,9 +3,14 @@ pub mod config;
 pub mod database;
 pub mod entities;
 pub mod handlers;
+pub mod matcher;
 
 pub use bot::BotManager;
 pub use config::Config;
 pub use database::DatabaseManager;
-pub use entities::group_reply;
+pub use entities::{
+    captcha_challenge, daily_hit, group_reply, group_role, group_setting, group_subscription,
+    ignored_user, keyword_alias, keyword_audit, keyword_stat, keyword_trash, moderation_log,
+    scheduled_message, user_session, user_warning,
+};
 pub use handlers::MessageHandler;
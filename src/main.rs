@@ -14,7 +14,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let db_manager = DatabaseManager::new(&config.database_url).await?;
 
     // 创建机器人管理器
-    let bot_manager = BotManager::new(&config.bot_token, db_manager.connection);
+    let bot_manager = BotManager::new(&config, db_manager.connection);
 
     // 验证 bot token
     bot_manager.validate_token().await?;
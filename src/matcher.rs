@@ -0,0 +1,177 @@
+use crate::entities::group_reply;
+use std::collections::HashMap;
+
+/// 关键词命中后的判定结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchStatus {
+    /// 满足全部条件，会发送回复
+    Matched,
+    /// 消息包含排除词
+    Excluded,
+    /// 消息缺少附加条件中的词
+    MissingRequired,
+}
+
+/// 一条关键词的命中情况
+#[derive(Clone, Debug)]
+pub struct KeywordMatch<'a> {
+    pub reply: &'a group_reply::Model,
+    /// 实际命中的触发词，可能是主关键词也可能是别名
+    pub trigger: &'a str,
+    /// 消息与触发词完全相同，此时触发消息也会被自动删除
+    pub exact: bool,
+    pub status: MatchStatus,
+}
+
+/// 按优先级（即回复的存储顺序）找出消息命中的所有关键词
+///
+/// 该函数不访问数据库也不发送消息，实际回复与 `/test` 预览共用同一套规则
+pub fn find_matches<'a>(
+    replies: &'a [group_reply::Model],
+    aliases: &'a HashMap<String, Vec<String>>,
+    text: &str,
+) -> Vec<KeywordMatch<'a>> {
+    let mut matches = Vec::new();
+
+    for reply in replies {
+        let triggers = std::iter::once(&reply.keywords)
+            .chain(aliases.get(&reply.keywords).into_iter().flatten())
+            .filter(|trigger| text.contains(trigger.as_str()));
+
+        // 优先取与消息完全相同的触发词
+        let mut hit: Option<&String> = None;
+        for trigger in triggers {
+            if text == trigger {
+                hit = Some(trigger);
+                break;
+            }
+            hit.get_or_insert(trigger);
+        }
+        let Some(trigger) = hit else {
+            continue;
+        };
+
+        // 主关键词命中后再检查排除词和附加条件
+        matches.push(KeywordMatch {
+            reply,
+            trigger,
+            exact: text == trigger,
+            status: check_conditions(reply, text),
+        });
+    }
+
+    matches
+}
+
+/// 返回第一条满足全部条件的命中，即实际会发送的回复
+pub fn first_match<'a>(matches: &[KeywordMatch<'a>]) -> Option<KeywordMatch<'a>> {
+    matches
+        .iter()
+        .find(|keyword_match| keyword_match.status == MatchStatus::Matched)
+        .cloned()
+}
+
+/// 检查排除词与附加条件：不能包含任何排除词，且必须包含所有附加条件中的词
+fn check_conditions(reply: &group_reply::Model, text: &str) -> MatchStatus {
+    let excluded = reply
+        .exclude_words
+        .as_deref()
+        .is_some_and(|words| condition_words(words).any(|word| text.contains(word)));
+    if excluded {
+        return MatchStatus::Excluded;
+    }
+
+    let required = reply
+        .require_words
+        .as_deref()
+        .is_none_or(|words| condition_words(words).all(|word| text.contains(word)));
+    if !required {
+        return MatchStatus::MissingRequired;
+    }
+
+    MatchStatus::Matched
+}
+
+/// 拆分以 | 分隔的条件词，空词会匹配任何消息，需要跳过
+fn condition_words(words: &str) -> impl Iterator<Item = &str> {
+    words
+        .split('|')
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(keywords: &str, exclude: Option<&str>, require: Option<&str>) -> group_reply::Model {
+        group_reply::Model {
+            group_id: 1,
+            keywords: keywords.to_string(),
+            reply: format!("{} 的回复", keywords),
+            exclude_words: exclude.map(str::to_string),
+            require_words: require.map(str::to_string),
+            cooldown_secs: None,
+            action: None,
+            action_duration_secs: None,
+        }
+    }
+
+    #[test]
+    fn exclude_words_block_match() {
+        let reply = reply("价格", Some("没有价格|不问价格"), None);
+        assert_eq!(check_conditions(&reply, "价格多少"), MatchStatus::Matched);
+        assert_eq!(
+            check_conditions(&reply, "我不问价格"),
+            MatchStatus::Excluded
+        );
+    }
+
+    #[test]
+    fn require_words_must_all_appear() {
+        let reply = reply("价格", None, Some("多少|钱"));
+        assert_eq!(check_conditions(&reply, "价格多少钱"), MatchStatus::Matched);
+        assert_eq!(
+            check_conditions(&reply, "价格多少"),
+            MatchStatus::MissingRequired
+        );
+    }
+
+    #[test]
+    fn empty_condition_words_are_ignored() {
+        let reply = reply("价格", Some("a||b|"), Some("|"));
+        assert_eq!(check_conditions(&reply, "价格"), MatchStatus::Matched);
+        assert_eq!(check_conditions(&reply, "价格 a"), MatchStatus::Excluded);
+    }
+
+    #[test]
+    fn find_matches_keeps_priority_order_and_prefers_exact_alias() {
+        let replies = vec![reply("群规", None, None), reply("规则", Some("不看"), None)];
+        let aliases = HashMap::from([(
+            "群规".to_string(),
+            vec!["规".to_string(), "群规则".to_string()],
+        )]);
+
+        let matches = find_matches(&replies, &aliases, "群规则");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].reply.keywords, "群规");
+        assert_eq!(matches[0].trigger, "群规则");
+        assert!(matches[0].exact);
+        assert_eq!(matches[1].reply.keywords, "规则");
+        assert!(!matches[1].exact);
+
+        let first = first_match(&matches).unwrap();
+        assert_eq!(first.reply.keywords, "群规");
+    }
+
+    #[test]
+    fn first_match_skips_filtered_replies() {
+        let replies = vec![reply("价格", Some("不问"), None), reply("问价", None, None)];
+        let aliases = HashMap::new();
+
+        let matches = find_matches(&replies, &aliases, "不问价格");
+        assert_eq!(matches[0].status, MatchStatus::Excluded);
+        assert_eq!(first_match(&matches).unwrap().reply.keywords, "问价");
+        assert!(find_matches(&replies, &aliases, "你好").is_empty());
+    }
+}